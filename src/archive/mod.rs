@@ -0,0 +1,107 @@
+use {
+    crate::{ ctx::Ctx, format, tree, },
+    std::{
+        fs::remove_file,
+        path::{ Path, PathBuf, },
+    },
+    todo_core::{ Container, GetPath, ItemType, },
+};
+/// `tasks.json` is archived to `tasks.archive.json` next to it.
+pub fn path_for(list: &Path) -> PathBuf {
+    let stem = list.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match list.extension() {
+        Some(ext) => format!("{}.archive.{}", stem, ext.to_string_lossy()),
+        None => format!("{}.archive", stem),
+    };
+    list.with_file_name(name)
+}
+fn archive_ctx(ctx: &Ctx) -> Ctx {
+    let mut archive_ctx = ctx.clone();
+    *archive_ctx.get_path_mut() = path_for(ctx.get_path());
+    archive_ctx
+}
+/// The archive as it was before a change, for putting it back.
+pub struct Snapshot {
+    ctx: Ctx,
+    /// `None` when there was no archive yet
+    container: Option<Container>,
+}
+impl Snapshot {
+    pub fn take(ctx: &Ctx) -> Result<Self, String> {
+        let mut archive_ctx = archive_ctx(ctx);
+        let container = if archive_ctx.get_path().exists() {
+            Some(format::load(&mut archive_ctx)?)
+        } else {
+            None
+        };
+        Ok(Self { ctx: archive_ctx, container, })
+    }
+    /// Writes the archive back as it was, removing it if it did not exist.
+    pub fn restore(self) -> Result<(), String> {
+        match self.container {
+            Some(mut container) => format::save(&self.ctx, &mut container),
+            None => remove_file(self.ctx.get_path()).map_err(|e| format!(
+                "Failed to remove \"{}\": {}", self.ctx.get_path().display(), e
+            )),
+        }
+    }
+}
+/// Finds or creates the chain of notes named by `texts` in the archive and
+/// returns the position of the innermost one.
+fn context_in(archive: &mut Container, texts: &Vec<String>) -> Result<Vec<usize>, String> {
+    let mut pos = Vec::new();
+    for text in texts.iter() {
+        let existing = tree::children_at(&archive.list.items, &pos)
+            .and_then(|children| children.iter().position(|item| item.text.eq(text)));
+        pos = match existing {
+            Some(i) => {
+                let mut next = pos.clone();
+                next.push(i);
+                next
+            },
+            None => tree::push_item(archive, &pos, ItemType::Note, text)
+                .ok_or("Failed to add context to the archive".to_string())?,
+        };
+    }
+    Ok(pos)
+}
+/// Moves the items at `positions` (in display order) out of `container` and
+/// into the list's archive, under notes named after their original parents,
+/// then saves the archive. The caller is responsible for saving `container`,
+/// and for restoring a `Snapshot` of the archive if that fails.
+pub fn archive_items(
+    ctx: &Ctx, container: &mut Container, positions: &Vec<Vec<usize>>,
+) -> Result<usize, String> {
+    let mut archive_ctx = archive_ctx(ctx);
+    let mut archive = if archive_ctx.get_path().exists() {
+        format::load(&mut archive_ctx)?
+    } else {
+        format::create(&mut archive_ctx)?
+    };
+    let mut contexts = Vec::new();
+    for pos in positions.iter() {
+        if tree::item_at(&container.list.items, pos).is_none() {
+            return Err("Failed to find the item to archive".to_string());
+        }
+        let mut texts = Vec::new();
+        for depth in 1..pos.len() {
+            match tree::item_at(&container.list.items, &pos[0..depth]) {
+                Some(parent) => texts.push(parent.text.clone()),
+                None => return Err("Failed to find the item's parent".to_string()),
+            }
+        }
+        contexts.push(texts);
+    }
+    let removed = tree::remove_items(&mut container.list.items, positions);
+    let count = removed.len();
+    for (texts, item) in contexts.into_iter().zip(removed.into_iter()) {
+        let context = context_in(&mut archive, &texts)?;
+        tree::children_at_mut(&mut archive.list.items, &context)
+            .ok_or("Failed to find the archive context".to_string())?
+            .push(item);
+    }
+    format::save(&archive_ctx, &mut archive)?;
+    Ok(count)
+}
@@ -1,20 +1,78 @@
-use clap::Parser;
+use clap::{ ArgEnum, Parser, };
 #[derive(Parser, Clone)]
 pub struct PathArgs {
     #[clap()]
     pub list_path: String,
 }
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+pub enum ListFormat {
+    Json,
+}
+#[derive(ArgEnum, Clone)]
+pub enum ConvFormat {
+    TodoTxt,
+    Org,
+    Html,
+    Csv,
+    Tsv,
+}
+#[derive(Parser, Clone)]
+pub struct ConvArgs {
+    #[clap()]
+    pub list_path: String,
+    #[clap()]
+    pub file_path: String,
+    #[clap(short, long, arg_enum)]
+    pub format: ConvFormat,
+}
 #[derive(Parser, Clone)]
 pub enum Mode {
     New(PathArgs),
     Open(PathArgs),
+    Import(ConvArgs),
+    Export(ConvArgs),
+    /// Opens a list's archive of completed items, read-only
+    Archive(PathArgs),
 }
 #[derive(Parser, Clone)]
 pub struct Args {
     #[clap(short, long)]
     pub debug: bool,
+    /// Turns debug off even when the config turns it on
+    #[clap(long, conflicts_with = "debug")]
+    pub no_debug: bool,
     #[clap(short='s', long)]
     pub display_hidden: bool,
+    /// Hides hidden items even when the config displays them
+    #[clap(long, conflicts_with = "display-hidden")]
+    pub no_display_hidden: bool,
+    /// Config file to use instead of the one in the config directory
+    #[clap(long)]
+    pub config: Option<String>,
+    /// Storage format of the list, detected from the path when omitted
+    #[clap(long, arg_enum)]
+    pub format: Option<ListFormat>,
+    /// Opens the nearest project list or the default list when omitted
     #[clap(subcommand)]
-    pub mode: Mode,
+    pub mode: Option<Mode>,
+}
+impl Args {
+    /// Whether debug is on, the command line taking precedence over `config`
+    pub fn debug_or(&self, config: bool) -> bool {
+        resolve(self.debug, self.no_debug, config)
+    }
+    /// Whether hidden items are displayed, the command line taking precedence
+    /// over `config`
+    pub fn display_hidden_or(&self, config: bool) -> bool {
+        resolve(self.display_hidden, self.no_display_hidden, config)
+    }
+}
+fn resolve(on: bool, off: bool, config: bool) -> bool {
+    if on {
+        true
+    } else if off {
+        false
+    } else {
+        config
+    }
 }
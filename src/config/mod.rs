@@ -0,0 +1,112 @@
+use {
+    crate::{ dirs, keys::{ KeyNames, Keymap, }, theme::{ ColorNames, Theme, }, },
+    serde::Deserialize,
+    std::{ fs::read_to_string, path::PathBuf, },
+};
+const CONFIG_FILE: &str = "config.toml";
+/// How the completion of a parent's todo children is shown
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressStyle {
+    /// `(3/7)`
+    Count,
+    /// A short bar followed by the count
+    Bar,
+    #[serde(alias = "none")]
+    Off,
+}
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Milliseconds between redraws
+    pub tick: u64,
+    /// Columns of indentation per level of nesting
+    pub indent: u16,
+    /// Height of the debug panel, borders included
+    pub debug_height: u16,
+    pub display_hidden: bool,
+    pub debug: bool,
+    /// Show the detail pane on start
+    pub detail_pane: bool,
+    pub progress: ProgressStyle,
+    /// Leave disabled todos out of progress figures
+    pub progress_ignore_disabled: bool,
+    /// Complete a parent once all of its todo children are complete, and
+    /// reopen it when one of them is reopened
+    pub auto_complete_parents: bool,
+    /// Offer to complete all descendants when a parent is completed
+    pub complete_descendants: bool,
+    /// Ask before removing items without children as well as parents
+    pub confirm_leaf_removal: bool,
+    /// One of the built-in themes, ignored when `NO_COLOR` is set
+    pub theme: String,
+    pub colors: ColorNames,
+    pub keys: KeyNames,
+    #[serde(skip)]
+    pub styles: Theme,
+    #[serde(skip)]
+    pub keymap: Keymap,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tick: 200,
+            indent: 4,
+            debug_height: 6,
+            display_hidden: false,
+            debug: false,
+            detail_pane: false,
+            progress: ProgressStyle::Count,
+            progress_ignore_disabled: false,
+            auto_complete_parents: false,
+            complete_descendants: false,
+            confirm_leaf_removal: true,
+            theme: "dark".to_string(),
+            colors: ColorNames::default(),
+            keys: KeyNames::default(),
+            styles: Theme::default(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+impl Config {
+    fn validate(&mut self) -> Result<(), String> {
+        if self.tick < 10 || self.tick > 5000 {
+            return Err(format!("tick: {} is outside of 10..=5000", self.tick));
+        }
+        if self.indent > 16 {
+            return Err(format!("indent: {} is larger than 16", self.indent));
+        }
+        if self.debug_height < 3 {
+            return Err(format!("debug_height: {} is smaller than 3", self.debug_height));
+        }
+        self.styles = Theme::load(&self.theme, &self.colors)?;
+        self.keymap = Keymap::from_config(&self.keys).map_err(|e| format!("keys: {}", e))?;
+        Ok(())
+    }
+    /// Loads the config from `path`, or from the config directory when no
+    /// path is given. A missing default config falls back to the built-in
+    /// defaults; a missing explicit config is an error.
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(p) => (PathBuf::from(p), true),
+            None => match dirs::config_dir() {
+                Some(dir) => (dir.join(CONFIG_FILE), false),
+                None => return Ok(Self::default()),
+            },
+        };
+        if !path.exists() {
+            if required {
+                return Err(format!("Config file \"{}\" does not exist", path.display()));
+            }
+            return Ok(Self::default());
+        }
+        let contents = read_to_string(&path)
+            .map_err(|e| format!("Failed to read config \"{}\": {}", path.display(), e))?;
+        let mut config: Self = toml::from_str(&contents)
+            .map_err(|e| format!("Invalid config \"{}\": {}", path.display(), e))?;
+        config.validate()
+            .map_err(|e| format!("Invalid config \"{}\": {}", path.display(), e))?;
+        Ok(config)
+    }
+}
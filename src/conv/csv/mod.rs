@@ -0,0 +1,190 @@
+use {
+    crate::tree::{ self, ItemVisitor, PrintCoords, },
+    std::collections::HashMap,
+    todo_core::{ Container, Item, ItemStatus, ItemType, },
+};
+const HEADER: [&str; 7] = [
+    "path", "depth", "parent", "type", "status", "hidden", "text",
+];
+fn quote(field: &str, delim: char) -> String {
+    if field.contains(delim) || field.contains('"') || field.contains('\n')
+        || field.contains('\r')
+    {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+fn to_row(fields: &[String], delim: char) -> String {
+    let mut row = fields.iter()
+        .map(|field| quote(field, delim))
+        .collect::<Vec<String>>()
+        .join(&delim.to_string());
+    row.push('\n');
+    row
+}
+struct RowBuilder {
+    delim: char,
+    parents: Vec<String>,
+    out: String,
+}
+impl ItemVisitor for RowBuilder {
+    fn enter(&mut self, item: &Item, pos: &Vec<usize>) {
+        let item_type = match item.item_type {
+            ItemType::Todo => "todo",
+            ItemType::Note => "note",
+        };
+        let status = match item.status {
+            ItemStatus::Complete => "complete",
+            ItemStatus::Incomplete => "incomplete",
+            ItemStatus::Disabled => "disabled",
+        };
+        let fields = [
+            pos.to_coords(),
+            pos.len().to_string(),
+            self.parents.last().cloned().unwrap_or_default(),
+            item_type.to_string(),
+            status.to_string(),
+            item.hidden.to_string(),
+            item.text.clone(),
+        ];
+        self.out.push_str(&to_row(&fields, self.delim));
+        self.parents.push(item.text.clone());
+    }
+    fn leave(&mut self, _item: &Item, _pos: &Vec<usize>) {
+        self.parents.pop();
+    }
+}
+/// Writes one row per item, hidden items included, with a header row naming
+/// the columns.
+pub fn export(container: &Container, delim: char) -> String {
+    let header = HEADER.iter().map(|h| h.to_string()).collect::<Vec<String>>();
+    let mut builder = RowBuilder {
+        delim, parents: Vec::new(), out: to_row(&header, delim),
+    };
+    tree::walk(&container.list.items, true, &mut builder);
+    builder.out
+}
+fn parse_rows(input: &str, delim: char) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' {
+            in_quotes = true;
+        } else if c == delim {
+            row.push(field);
+            field = String::new();
+        } else if c == '\n' {
+            row.push(field);
+            field = String::new();
+            rows.push(row);
+            row = Vec::new();
+        } else if c != '\r' {
+            field.push(c);
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+fn parse_path(value: &str, row_no: usize) -> Result<Vec<usize>, String> {
+    let mut path = Vec::new();
+    for coord in value.split(',') {
+        match coord.trim().parse::<usize>() {
+            Ok(c) => path.push(c),
+            Err(_) => return Err(format!("Row {}: invalid path \"{}\"", row_no, value)),
+        }
+    }
+    Ok(path)
+}
+/// Rebuilds a tree from rows produced by `export`. Rows are placed by their
+/// path column, so they may appear in any order; the depth and parent columns
+/// are informational. Returns the number of items added.
+pub fn import(container: &mut Container, input: &str, delim: char) -> Result<usize, String> {
+    let rows = parse_rows(input, delim)?;
+    let mut entries = Vec::new();
+    let mut row_no = 0;
+    for row in rows.iter() {
+        row_no = row_no + 1;
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        if row_no == 1 && row[0].eq(HEADER[0]) {
+            continue;
+        }
+        if row.len() != HEADER.len() {
+            return Err(format!(
+                "Row {}: expected {} columns, found {}", row_no, HEADER.len(), row.len()
+            ));
+        }
+        let path = parse_path(&row[0], row_no)?;
+        let item_type = match row[3].to_lowercase().as_str() {
+            "todo" => ItemType::Todo,
+            "note" => ItemType::Note,
+            other => return Err(format!("Row {}: invalid type \"{}\"", row_no, other)),
+        };
+        let status = match row[4].to_lowercase().as_str() {
+            "complete" => ItemStatus::Complete,
+            "incomplete" => ItemStatus::Incomplete,
+            "disabled" => ItemStatus::Disabled,
+            other => return Err(format!("Row {}: invalid status \"{}\"", row_no, other)),
+        };
+        let hidden = match row[5].to_lowercase().as_str() {
+            "true" | "1" | "yes" => true,
+            "false" | "0" | "no" | "" => false,
+            other => return Err(format!("Row {}: invalid hidden flag \"{}\"", row_no, other)),
+        };
+        entries.push((path, row_no, item_type, status, hidden, row[6].clone()));
+    }
+    // parents sort ahead of their children and siblings keep their order
+    entries.sort_by(|x, y| x.0.cmp(&y.0));
+    // the sort is stable, so a repeated path follows the row it repeats
+    for pair in entries.windows(2) {
+        if pair[0].0.eq(&pair[1].0) {
+            return Err(format!(
+                "Row {}: path is already used on row {}", pair[1].1, pair[0].1
+            ));
+        }
+    }
+    let mut placed: HashMap<Vec<usize>, Vec<usize>> = HashMap::new();
+    let mut count = 0;
+    for (path, row_no, item_type, status, hidden, text) in entries {
+        let orig_parent = path[0..path.len() - 1].to_vec();
+        let parent = if orig_parent.is_empty() {
+            Vec::new()
+        } else {
+            match placed.get(&orig_parent) {
+                Some(p) => p.clone(),
+                None => return Err(format!("Row {}: parent of item is missing", row_no)),
+            }
+        };
+        let new_pos = tree::push_item(container, &parent, item_type, &text)
+            .ok_or(format!("Row {}: failed to add item", row_no))?;
+        let item = tree::item_at_mut(&mut container.list.items, &new_pos)
+            .ok_or(format!("Row {}: failed to find added item", row_no))?;
+        item.status = status;
+        item.hidden = hidden;
+        placed.insert(path, new_pos);
+        count = count + 1;
+    }
+    Ok(count)
+}
@@ -0,0 +1,100 @@
+use {
+    crate::tree::{ self, ItemVisitor, },
+    todo_core::{ Container, Item, ItemStatus, ItemType, },
+};
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+ul { list-style: none; padding-left: 1.5em; }
+summary { cursor: pointer; }
+.note { font-style: italic; }
+.disabled > .text, .disabled > details > summary > .text { text-decoration: line-through; }
+.hidden { color: #999; }
+.progress { color: #666; font-size: 0.9em; margin-left: 0.5em; }
+";
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+fn progress_span(items: &Vec<Item>) -> String {
+    let (complete, total) = tree::progress(items, false);
+    if total == 0 {
+        return String::new();
+    }
+    format!("<span class=\"progress\">({}/{})</span>", complete, total)
+}
+struct HtmlBuilder {
+    out: String,
+}
+impl HtmlBuilder {
+    fn item_line(item: &Item) -> String {
+        let marker = match item.item_type {
+            ItemType::Todo => match item.status {
+                ItemStatus::Complete => "<input type=\"checkbox\" disabled checked> ",
+                ItemStatus::Incomplete | ItemStatus::Disabled => {
+                    "<input type=\"checkbox\" disabled> "
+                },
+            },
+            ItemType::Note => "",
+        };
+        format!("{}<span class=\"text\">{}</span>", marker, escape(&item.text))
+    }
+    fn classes(item: &Item) -> String {
+        let mut classes = Vec::new();
+        match item.item_type {
+            ItemType::Todo => match item.status {
+                ItemStatus::Complete => classes.push("complete"),
+                ItemStatus::Incomplete => classes.push("incomplete"),
+                ItemStatus::Disabled => classes.push("disabled"),
+            },
+            ItemType::Note => classes.push("note"),
+        }
+        if item.hidden {
+            classes.push("hidden");
+        }
+        classes.join(" ")
+    }
+}
+impl ItemVisitor for HtmlBuilder {
+    fn enter(&mut self, item: &Item, _pos: &Vec<usize>) {
+        self.out.push_str(&format!("<li class=\"{}\">", Self::classes(item)));
+        if item.sub_items.is_empty() {
+            self.out.push_str(&Self::item_line(item));
+        } else {
+            self.out.push_str(&format!(
+                "<details open><summary>{}{}</summary>\n<ul>\n",
+                Self::item_line(item), progress_span(&item.sub_items),
+            ));
+        }
+    }
+    fn leave(&mut self, item: &Item, _pos: &Vec<usize>) {
+        if !item.sub_items.is_empty() {
+            self.out.push_str("</ul>\n</details>");
+        }
+        self.out.push_str("</li>\n");
+    }
+}
+/// Renders the list, hidden items included, as a single self-contained HTML
+/// page.
+pub fn export(container: &Container, title: &str) -> String {
+    let mut builder = HtmlBuilder { out: String::new(), };
+    tree::walk(&container.list.items, true, &mut builder);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+        <title>{title}</title>\n<style>\n{style}</style>\n</head>\n<body>\n\
+        <h1>{title}{progress}</h1>\n<ul>\n{items}</ul>\n</body>\n</html>\n",
+        title = escape(title),
+        style = STYLE,
+        progress = progress_span(&container.list.items),
+        items = builder.out,
+    )
+}
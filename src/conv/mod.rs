@@ -0,0 +1,49 @@
+mod csv;
+mod html;
+mod org;
+mod todotxt;
+use {
+    crate::{ args::{ ConvArgs, ConvFormat, }, ctx::Ctx, format, },
+    std::fs::{ read_to_string, write, },
+    todo_core::GetPath,
+};
+/// Reads `args.file_path` in the requested format and appends its items to
+/// the list, creating the list when it does not exist yet.
+pub fn import(ctx: &mut Ctx, args: &ConvArgs) -> Result<usize, String> {
+    let input = read_to_string(&args.file_path)
+        .map_err(|e| format!("Failed to read \"{}\": {}", args.file_path, e))?;
+    let mut container = if ctx.get_path().exists() {
+        format::load(ctx)?
+    } else {
+        ctx.create_parent_dir()
+            .map_err(|e| format!("Failed to create list directory: {}", e))?;
+        format::create(ctx)?
+    };
+    let count = match args.format {
+        ConvFormat::TodoTxt => todotxt::import(&mut container, &input)?,
+        ConvFormat::Org => org::import(&mut container, &input)?,
+        ConvFormat::Html => return Err("HTML is an export-only format".to_string()),
+        ConvFormat::Csv => csv::import(&mut container, &input, ',')?,
+        ConvFormat::Tsv => csv::import(&mut container, &input, '\t')?,
+    };
+    format::save(ctx, &mut container)?;
+    Ok(count)
+}
+/// Writes the list to `args.file_path` in the requested format.
+pub fn export(ctx: &mut Ctx, args: &ConvArgs) -> Result<(), String> {
+    let container = format::load(ctx)?;
+    let output = match args.format {
+        ConvFormat::TodoTxt => todotxt::export(&container),
+        ConvFormat::Org => org::export(&container),
+        ConvFormat::Html => {
+            let title = ctx.get_path().file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            html::export(&container, &title)
+        },
+        ConvFormat::Csv => csv::export(&container, ','),
+        ConvFormat::Tsv => csv::export(&container, '\t'),
+    };
+    write(&args.file_path, output)
+        .map_err(|e| format!("Failed to write \"{}\": {}", args.file_path, e))
+}
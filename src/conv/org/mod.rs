@@ -0,0 +1,271 @@
+use {
+    crate::tree,
+    todo_core::{ Container, Item, ItemStatus, ItemType, },
+};
+// CANCELED has no meaning in a default org setup, so exported files declare it
+// as a done-state keyword for disabled items.
+const TODO_KEYWORDS: &str = "#+TODO: TODO | DONE CANCELED";
+const ARCHIVE_TAG: &str = "ARCHIVE";
+// List items always nest below the headline they appear under
+const LIST_LEVEL: usize = 1000;
+const KEYWORDS: [&str; 4] = [ "TODO", "DONE", "CANCELED", "CANCELLED", ];
+fn is_tags(word: &str) -> bool {
+    word.len() > 2 && word.starts_with(':') && word.ends_with(':')
+}
+/// Whether a backslash at the start of `word` was put there by `escape`
+fn is_escaped(word: &str) -> bool {
+    match word.strip_prefix('\\') {
+        Some(rest) => rest.starts_with('\\') || is_tags(rest) || KEYWORDS.contains(&rest),
+        None => false,
+    }
+}
+/// Puts a backslash before a first word that would be read as a todo keyword
+/// and before a last word that would be read as tags. Words already starting
+/// with a backslash get one too.
+fn escape(text: &str) -> String {
+    let (head, last) = match text.rfind(' ') {
+        Some(i) => (&text[..=i], &text[i + 1..]),
+        None => ("", text),
+    };
+    let mut out = head.to_string();
+    if is_tags(last) || last.starts_with('\\') {
+        out.push('\\');
+    }
+    out.push_str(last);
+    let first = out.split(' ').next().unwrap_or("");
+    if KEYWORDS.contains(&first) || first.starts_with('\\') {
+        out.insert(0, '\\');
+    }
+    out
+}
+/// Undoes `escape`, leaving other backslashes alone.
+fn unescape(text: &str) -> String {
+    let first = text.split(' ').next().unwrap_or("");
+    let text = if is_escaped(first) { &text[1..] } else { text };
+    match text.rfind(' ') {
+        Some(i) if is_escaped(&text[i + 1..]) => format!("{}{}", &text[..=i], &text[i + 2..]),
+        None if is_escaped(text) => text[1..].to_string(),
+        _ => text.to_string(),
+    }
+}
+fn to_headline(item: &Item, depth: usize) -> String {
+    let mut line = "*".repeat(depth);
+    match item.item_type {
+        ItemType::Todo => match item.status {
+            ItemStatus::Complete => line.push_str(" DONE"),
+            ItemStatus::Incomplete => line.push_str(" TODO"),
+            ItemStatus::Disabled => line.push_str(" CANCELED"),
+        },
+        ItemType::Note => {},
+    }
+    line.push(' ');
+    line.push_str(&escape(&item.text.replace('\n', " ")));
+    if item.hidden {
+        line.push_str(&format!(" :{}:", ARCHIVE_TAG));
+    }
+    line
+}
+fn export_items(items: &Vec<Item>, depth: usize, out: &mut String) {
+    for item in items.iter() {
+        out.push_str(&to_headline(item, depth));
+        out.push('\n');
+        export_items(&item.sub_items, depth + 1, out);
+    }
+}
+/// Renders the list as an org document with one headline per item.
+pub fn export(container: &Container) -> String {
+    let mut out = String::new();
+    out.push_str(TODO_KEYWORDS);
+    out.push('\n');
+    export_items(&container.list.items, 1, &mut out);
+    out
+}
+struct Entry {
+    level: usize,
+    item_type: ItemType,
+    status: ItemStatus,
+    hidden: bool,
+    text: String,
+}
+impl Entry {
+    fn headline(line: &str) -> Option<Self> {
+        let level = line.chars().take_while(|c| *c == '*').count();
+        if level == 0 || !line[level..].starts_with(' ') {
+            return None;
+        }
+        // split on single spaces so that repeated ones stay in the text
+        let mut tokens = line[level + 1..].split(' ').collect::<Vec<&str>>();
+        let mut item_type = ItemType::Note;
+        let mut status = ItemStatus::Incomplete;
+        match tokens.first() {
+            Some(&"TODO") => {
+                item_type = ItemType::Todo;
+                tokens.remove(0);
+            },
+            Some(&"DONE") => {
+                item_type = ItemType::Todo;
+                status = ItemStatus::Complete;
+                tokens.remove(0);
+            },
+            Some(&"CANCELED") | Some(&"CANCELLED") => {
+                item_type = ItemType::Todo;
+                status = ItemStatus::Disabled;
+                tokens.remove(0);
+            },
+            _ => {},
+        }
+        let mut hidden = false;
+        let mut tag_token = None;
+        let mut tagged = false;
+        if let Some(last) = tokens.last() {
+            if is_tags(last) {
+                tagged = true;
+                let tags = last[1..last.len() - 1].split(':')
+                    .filter(|tag| {
+                        if tag.eq(&ARCHIVE_TAG) {
+                            hidden = true;
+                            false
+                        } else {
+                            true
+                        }
+                    })
+                    .collect::<Vec<&str>>();
+                tokens.pop();
+                if !tags.is_empty() {
+                    tag_token = Some(format!(":{}:", tags.join(":")));
+                }
+            }
+        }
+        let mut text = tokens.join(" ");
+        if tagged {
+            // org aligns tags with any number of spaces
+            text.truncate(text.trim_end().len());
+        }
+        let mut text = unescape(&text);
+        if let Some(tags) = tag_token {
+            text.push(' ');
+            text.push_str(&tags);
+        }
+        Some(Self { level, item_type, status, hidden, text, })
+    }
+    fn list_item(line: &str) -> Option<Self> {
+        let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+        let rest = line.trim_start();
+        let body = if rest.starts_with("- ") || rest.starts_with("+ ") {
+            &rest[2..]
+        } else if indent > 0 && rest.starts_with("* ") {
+            &rest[2..]
+        } else {
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits > 0 && (rest[digits..].starts_with(". ") || rest[digits..].starts_with(") ")) {
+                &rest[digits + 2..]
+            } else {
+                return None;
+            }
+        };
+        let (item_type, status, text) = if let Some(text) = body.strip_prefix("[ ] ") {
+            (ItemType::Todo, ItemStatus::Incomplete, text)
+        } else if let Some(text) = body.strip_prefix("[-] ") {
+            (ItemType::Todo, ItemStatus::Incomplete, text)
+        } else if let Some(text) = body.strip_prefix("[X] ").or(body.strip_prefix("[x] ")) {
+            (ItemType::Todo, ItemStatus::Complete, text)
+        } else {
+            (ItemType::Note, ItemStatus::Incomplete, body)
+        };
+        Some(Self {
+            level: LIST_LEVEL + indent, item_type, status, hidden: false,
+            text: text.trim().to_string(),
+        })
+    }
+}
+/// Appends the headlines and plain list items of an org document to the
+/// container. Headline depth and list indentation become nesting; other body
+/// text is ignored. Returns the number of items added.
+pub fn import(container: &mut Container, input: &str) -> Result<usize, String> {
+    let mut stack: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut count = 0;
+    let mut line_no = 0;
+    for line in input.lines() {
+        line_no = line_no + 1;
+        let entry = match Entry::headline(line).or_else(|| Entry::list_item(line)) {
+            Some(e) => e,
+            None => continue,
+        };
+        // list items outside of any headline still nest by their indentation
+        while let Some((level, _)) = stack.last() {
+            if *level >= entry.level {
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+        let parent = match stack.last() {
+            Some((_, pos)) => pos.clone(),
+            None => Vec::new(),
+        };
+        let new_pos = tree::push_item(container, &parent, entry.item_type, &entry.text)
+            .ok_or(format!("Line {}: failed to add item", line_no))?;
+        let item = tree::item_at_mut(&mut container.list.items, &new_pos)
+            .ok_or(format!("Line {}: failed to find added item", line_no))?;
+        item.status = entry.status;
+        item.hidden = entry.hidden;
+        stack.push((entry.level, new_pos));
+        count = count + 1;
+    }
+    Ok(count)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// The headline `to_headline` writes for a note, read back
+    fn round_trip(keyword: &str, text: &str, hidden: bool) -> Entry {
+        let mut line = format!("**{} {}", keyword, escape(text));
+        if hidden {
+            line.push_str(&format!(" :{}:", ARCHIVE_TAG));
+        }
+        Entry::headline(&line).unwrap()
+    }
+    #[test]
+    fn note_text_round_trips() {
+        let texts = [
+            "plain",
+            "TODO is not a keyword here",
+            "DONE",
+            "CANCELED plans",
+            "ends in :tag:",
+            ":only:tags:",
+            "repeated   spaces  inside",
+            " leading space",
+            "\\TODO already escaped",
+            "a \\",
+            "",
+        ];
+        for text in texts.iter() {
+            for hidden in [ false, true, ] {
+                let entry = round_trip("", text, hidden);
+                assert!(matches!(entry.item_type, ItemType::Note), "{}", text);
+                assert_eq!(entry.hidden, hidden);
+                assert_eq!(entry.level, 2);
+                assert_eq!(entry.text, *text);
+            }
+        }
+    }
+    #[test]
+    fn todo_text_round_trips() {
+        let entry = round_trip(" DONE", "TODO later :x:", false);
+        assert!(matches!(entry.status, ItemStatus::Complete));
+        assert_eq!(entry.text, "TODO later :x:");
+        let entry = round_trip(" CANCELED", "two  spaces", true);
+        assert!(matches!(entry.status, ItemStatus::Disabled));
+        assert!(entry.hidden);
+        assert_eq!(entry.text, "two  spaces");
+    }
+    #[test]
+    fn foreign_headlines_are_read() {
+        let entry = Entry::headline("* TODO Call \\alpha      :work:ARCHIVE:").unwrap();
+        assert!(matches!(entry.item_type, ItemType::Todo));
+        assert!(entry.hidden);
+        assert_eq!(entry.text, "Call \\alpha :work:");
+        assert!(Entry::headline("*bold* text").is_none());
+    }
+}
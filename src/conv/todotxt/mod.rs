@@ -0,0 +1,277 @@
+use {
+    crate::tree,
+    std::collections::HashMap,
+    todo_core::{ Container, Item, ItemStatus, ItemType, },
+};
+// Trailing key:value tags used to encode the fields todo.txt has no syntax
+// for. `h:1` follows the common todo.txt convention for hidden tasks.
+const POS_KEY: &str = "pos:";
+const TYPE_KEY: &str = "type:";
+const STATUS_KEY: &str = "status:";
+const HIDDEN_KEY: &str = "h:";
+fn is_date(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 10 && bytes.iter().enumerate().all(|(i, b)| {
+        match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        }
+    })
+}
+fn is_priority(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 3 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')'
+}
+fn is_tag(word: &str) -> bool {
+    [ POS_KEY, TYPE_KEY, STATUS_KEY, HIDDEN_KEY, ].iter().any(|key| word.starts_with(key))
+}
+/// Puts a backslash before a last word that would be read as one of our tags
+/// and before a first word that would be read as a completion mark, priority
+/// or date. Words already starting with a backslash get one too.
+fn escape(text: &str) -> String {
+    let (head, last) = match text.rfind(' ') {
+        Some(i) => (&text[..=i], &text[i + 1..]),
+        None => ("", text),
+    };
+    let mut out = head.to_string();
+    if is_tag(last) || last.starts_with('\\') {
+        out.push('\\');
+    }
+    out.push_str(last);
+    let first = out.split(' ').next().unwrap_or("");
+    if first.eq("x") || is_priority(first) || is_date(first) || first.starts_with('\\') {
+        out.insert(0, '\\');
+    }
+    out
+}
+/// Undoes `escape`.
+fn unescape(text: &str) -> String {
+    let text = text.strip_prefix('\\').unwrap_or(text);
+    let (head, last) = match text.rfind(' ') {
+        Some(i) => (&text[..=i], &text[i + 1..]),
+        None => ("", text),
+    };
+    format!("{}{}", head, last.strip_prefix('\\').unwrap_or(last))
+}
+fn to_line(item: &Item, pos: &Vec<usize>) -> String {
+    let mut line = String::new();
+    if let ItemStatus::Complete = item.status {
+        line.push_str("x ");
+    }
+    line.push_str(&escape(&item.text.replace('\n', " ")));
+    if let ItemType::Note = item.item_type {
+        line.push_str(&format!(" {}note", TYPE_KEY));
+    }
+    if let ItemStatus::Disabled = item.status {
+        line.push_str(&format!(" {}disabled", STATUS_KEY));
+    }
+    if item.hidden {
+        line.push_str(&format!(" {}1", HIDDEN_KEY));
+    }
+    let coords = pos.iter()
+        .map(|p| (p + 1).to_string())
+        .collect::<Vec<String>>()
+        .join(".");
+    line.push_str(&format!(" {}{}", POS_KEY, coords));
+    line
+}
+fn export_items(items: &Vec<Item>, pos: &mut Vec<usize>, out: &mut String) {
+    let mut i = 0;
+    for item in items.iter() {
+        pos.push(i);
+        out.push_str(&to_line(item, pos));
+        out.push('\n');
+        export_items(&item.sub_items, pos, out);
+        pos.pop();
+        i = i + 1;
+    }
+}
+/// Flattens the list into todo.txt lines in depth-first order. Each line
+/// carries a `pos:` tag with its dotted one-based position so that `import`
+/// can rebuild the hierarchy.
+pub fn export(container: &Container) -> String {
+    let mut out = String::new();
+    let mut pos = Vec::new();
+    export_items(&container.list.items, &mut pos, &mut out);
+    out
+}
+struct Line {
+    complete: bool,
+    text: String,
+    note: bool,
+    disabled: bool,
+    hidden: bool,
+    pos: Option<Vec<usize>>,
+}
+impl Line {
+    fn parse(raw: &str, line_no: usize) -> Result<Self, String> {
+        let mut rest = raw;
+        let complete = rest.starts_with("x ");
+        if complete {
+            rest = &rest[2..];
+            // drop the completion date, it has no place in an item
+            // `get` as the text may start with a character wider than a byte
+            if let (Some(date), Some(after)) = (rest.get(0..10), rest.get(10..)) {
+                if is_date(date) && after.starts_with(' ') {
+                    rest = &after[1..];
+                }
+            }
+        }
+        let mut tokens = rest.split(' ').collect::<Vec<&str>>();
+        let mut note = false;
+        let mut disabled = false;
+        let mut hidden = false;
+        let mut pos = None;
+        while let Some(token) = tokens.last() {
+            if let Some(value) = token.strip_prefix(POS_KEY) {
+                let mut coords = Vec::new();
+                for coord in value.split('.') {
+                    match coord.parse::<usize>() {
+                        Ok(c) if c > 0 => coords.push(c - 1),
+                        _ => return Err(format!(
+                            "Line {}: invalid position \"{}\"", line_no, value
+                        )),
+                    }
+                }
+                pos = Some(coords);
+            } else if let Some(value) = token.strip_prefix(TYPE_KEY) {
+                note = value.eq("note");
+            } else if let Some(value) = token.strip_prefix(STATUS_KEY) {
+                disabled = value.eq("disabled");
+            } else if let Some(value) = token.strip_prefix(HIDDEN_KEY) {
+                hidden = value.eq("1");
+            } else {
+                break;
+            }
+            tokens.pop();
+        }
+        let text = unescape(&tokens.join(" "));
+        Ok(Self { complete, text, note, disabled, hidden, pos, })
+    }
+    fn project(&self) -> Option<&str> {
+        self.text.split(' ')
+            .filter(|token| token.len() > 1 && token.starts_with('+'))
+            .map(|token| &token[1..])
+            .next()
+    }
+}
+/// Appends the todo.txt `input` to the container. Lines carrying a `pos:` tag
+/// are placed by that position; any other line is grouped under a root note
+/// named after its first `+project`, or added at the root. Contexts are left
+/// in the text as tags. Returns the number of items added.
+pub fn import(container: &mut Container, input: &str) -> Result<usize, String> {
+    let mut placed: HashMap<Vec<usize>, Vec<usize>> = HashMap::new();
+    let mut projects: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut count = 0;
+    let mut line_no = 0;
+    for raw in input.lines() {
+        line_no = line_no + 1;
+        if raw.trim().is_empty() {
+            continue;
+        }
+        let line = Line::parse(raw, line_no)?;
+        let parent = match &line.pos {
+            Some(pos) => {
+                let orig_parent = pos[0..pos.len() - 1].to_vec();
+                if orig_parent.is_empty() {
+                    Vec::new()
+                } else {
+                    match placed.get(&orig_parent) {
+                        Some(p) => p.clone(),
+                        None => return Err(format!(
+                            "Line {}: parent of item is missing", line_no
+                        )),
+                    }
+                }
+            },
+            None => match line.project() {
+                Some(project) => match projects.get(project) {
+                    Some(p) => p.clone(),
+                    None => {
+                        let existing = container.list.items.iter()
+                            .position(|item| item.text.eq(project));
+                        let p = match existing {
+                            Some(i) => vec![i],
+                            None => tree::push_item(
+                                container, &Vec::new(), ItemType::Note, project,
+                            ).ok_or(format!(
+                                "Line {}: failed to add project \"{}\"", line_no, project
+                            ))?,
+                        };
+                        projects.insert(project.to_string(), p.clone());
+                        p
+                    },
+                },
+                None => Vec::new(),
+            },
+        };
+        let item_type = if line.note { ItemType::Note } else { ItemType::Todo };
+        let new_pos = tree::push_item(container, &parent, item_type, &line.text)
+            .ok_or(format!("Line {}: failed to add item", line_no))?;
+        let item = tree::item_at_mut(&mut container.list.items, &new_pos)
+            .ok_or(format!("Line {}: failed to find added item", line_no))?;
+        item.hidden = line.hidden;
+        if line.complete {
+            item.status = ItemStatus::Complete;
+        } else if line.disabled {
+            item.status = ItemStatus::Disabled;
+        } else {
+            item.status = ItemStatus::Incomplete;
+        }
+        if let Some(pos) = line.pos {
+            placed.insert(pos, new_pos);
+        }
+        count = count + 1;
+    }
+    Ok(count)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn round_trip(text: &str, complete: bool) -> String {
+        let mark = if complete { "x " } else { "" };
+        let raw = format!("{}{} {}1", mark, escape(text), POS_KEY);
+        let line = Line::parse(&raw, 1).unwrap();
+        assert_eq!(line.complete, complete);
+        assert_eq!(line.pos, Some(vec![ 0, ]));
+        line.text
+    }
+    #[test]
+    fn text_round_trips() {
+        let texts = [
+            "plain text",
+            "x marks the spot",
+            "x",
+            "(A) not a priority",
+            "2024-01-02 is not a date",
+            "ends with h:1",
+            "ends with type:note",
+            "ends with status:disabled",
+            "pos:3",
+            "\\leading and trailing\\",
+            "\\x h:1",
+            "",
+        ];
+        for text in texts.iter() {
+            assert_eq!(round_trip(text, false), *text);
+            assert_eq!(round_trip(text, true), *text);
+        }
+    }
+    #[test]
+    fn tags_are_read() {
+        let line = Line::parse("x 2024-01-02 call mom type:note h:1 pos:2.1", 1).unwrap();
+        assert!(line.complete && line.note && line.hidden);
+        assert_eq!(line.text, "call mom");
+        assert_eq!(line.pos, Some(vec![ 1, 0, ]));
+    }
+    #[test]
+    fn completed_wide_text_is_read() {
+        let line = Line::parse("x 日本語のテキスト pos:1", 1).unwrap();
+        assert!(line.complete);
+        assert_eq!(line.text, "日本語のテキスト");
+        let line = Line::parse("x 2024-01-02 日本語 pos:1", 1).unwrap();
+        assert_eq!(line.text, "日本語");
+        assert_eq!(round_trip("日本語のテキスト", true), "日本語のテキスト");
+        assert_eq!(round_trip("é", true), "é");
+    }
+}
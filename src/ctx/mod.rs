@@ -1,36 +1,70 @@
 use {
-    crate::args::{ Args, Mode, },
-    std::path::PathBuf,
+    crate::{ archive, args::{ Args, ListFormat, Mode, }, config::Config, dirs, format, },
+    std::{ fs::create_dir_all, io::Error as IOError, path::PathBuf, },
     todo_core::GetPath,
 };
 #[derive(Clone)]
 pub struct Ctx {
     pub args: Args,
+    pub config: Config,
+    pub format: ListFormat,
     path: PathBuf,
 }
 impl Ctx {
-    pub fn new(args: Args) -> Self {
-        Self { args, path: PathBuf::new(), }
+    pub fn new(args: Args, config: Config) -> Self {
+        Self { args, config, format: ListFormat::Json, path: PathBuf::new(), }
     }
     pub fn construct_path(&mut self) {
         let path = match &self.args.mode {
-            Mode::Open(args) => {
+            Some(Mode::Open(args)) => {
                 &args.list_path
             },
-            Mode::New(args) => {
+            Some(Mode::New(args)) | Some(Mode::Archive(args)) => {
                 &args.list_path
             },
+            Some(Mode::Import(args)) | Some(Mode::Export(args)) => {
+                &args.list_path
+            },
+            None => {
+                self.path.push(dirs::default_list());
+                return;
+            },
         };
-        let tmp_path = PathBuf::from(format!("{}", &path));
+        let tmp_path = dirs::resolve_name(path);
         match tmp_path.extension() {
-            Some(ext) => {
-                if !ext.eq("json") {
-                    self.path.push(format!("{}.json", &path));
-                } else {
-                    self.path.push(format!("{}", &path));
-                }
+            Some(_) => self.path.push(tmp_path),
+            None => self.path.push(format!("{}.json", tmp_path.display())),
+        }
+        if self.read_only() {
+            self.path = archive::path_for(&self.path);
+        }
+    }
+    /// The archive view must not change the archive
+    pub fn read_only(&self) -> bool {
+        matches!(self.args.mode, Some(Mode::Archive(_)))
+    }
+    /// Whether a new list should be written before opening it: always for
+    /// `new`, and for the default list the first time it is used.
+    pub fn should_create(&self) -> bool {
+        match &self.args.mode {
+            Some(Mode::New(_)) => true,
+            None => !self.path.exists(),
+            _ => false,
+        }
+    }
+    pub fn detect_format(&mut self) -> Result<(), String> {
+        self.format = format::detect(&self.path, self.args.format)?;
+        Ok(())
+    }
+    /// Creates any missing parent directories of the list path, returning the
+    /// directory when one had to be created.
+    pub fn create_parent_dir(&self) -> Result<Option<PathBuf>, IOError> {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !parent.exists() => {
+                create_dir_all(parent)?;
+                Ok(Some(parent.to_path_buf()))
             },
-            None => self.path.push(format!("{}.json", &path)),
+            _ => Ok(None),
         }
     }
 }
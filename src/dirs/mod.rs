@@ -0,0 +1,64 @@
+use std::{ env, path::{ Path, PathBuf, }, };
+const APP_DIR: &str = "todo-tui";
+/// Name of the per-project list looked up from the current directory upwards
+pub const PROJECT_LIST: &str = ".todo.json";
+/// Name of the list opened when no path is given and no project list exists
+pub const DEFAULT_LIST: &str = "default.json";
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    match env::var_os(var) {
+        // the spec says relative paths are invalid and should be ignored
+        Some(dir) if Path::new(&dir).is_absolute() => Some(PathBuf::from(dir)),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)),
+    }
+}
+/// `$XDG_DATA_HOME/todo-tui`, falling back to `~/.local/share/todo-tui`
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join(APP_DIR))
+}
+/// `$XDG_CONFIG_HOME/todo-tui`, falling back to `~/.config/todo-tui`
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join(APP_DIR))
+}
+/// Walks up from the current directory looking for a project list.
+pub fn find_project_list() -> Option<PathBuf> {
+    let mut dir = env::current_dir().ok()?;
+    loop {
+        let candidate = dir.join(PROJECT_LIST);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+/// The list used when no path is given: the nearest project list, otherwise
+/// the default list in the data directory.
+pub fn default_list() -> PathBuf {
+    if let Some(path) = find_project_list() {
+        return path;
+    }
+    match data_dir() {
+        Some(dir) => dir.join(DEFAULT_LIST),
+        None => PathBuf::from(PROJECT_LIST),
+    }
+}
+/// Resolves a bare list name such as `work` to `work.json` in the data
+/// directory, unless a list of that name exists in the current directory.
+/// Anything that looks like a path is returned unchanged.
+pub fn resolve_name(name: &str) -> PathBuf {
+    let path = PathBuf::from(name);
+    let is_bare = path.extension().is_none() && path.components().count() == 1
+        && !name.starts_with('.');
+    if !is_bare {
+        return path;
+    }
+    let local = PathBuf::from(format!("{}.json", name));
+    if local.exists() {
+        return local;
+    }
+    match data_dir() {
+        Some(dir) => dir.join(format!("{}.json", name)),
+        None => local,
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    env,
+    fs::{ read_to_string, remove_file, File, OpenOptions, },
+    io::{ ErrorKind, Write, },
+    path::PathBuf,
+    process::{ Command, id as process_id, },
+    time::{ SystemTime, UNIX_EPOCH, },
+};
+const FALLBACK_EDITOR: &str = "vi";
+const CREATE_ATTEMPTS: u32 = 16;
+/// `$VISUAL`, then `$EDITOR`, split into the program and its arguments
+fn editor_command() -> (String, Vec<String>) {
+    let command = env::var("VISUAL").ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| env::var("EDITOR").ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or(FALLBACK_EDITOR.to_string());
+    let mut parts = command.split_whitespace().map(|part| part.to_string());
+    let program = parts.next().unwrap_or(FALLBACK_EDITOR.to_string());
+    (program, parts.collect())
+}
+/// Creates a new file in the temporary directory that only the user can
+/// read. An existing file or link at the chosen name is never opened, so
+/// another user can't plant one there; a taken name is retried.
+fn create_temp(extension: &str) -> Result<(PathBuf, File), String> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    let mut attempt = 0;
+    loop {
+        let name = format!("todo-tui-{}-{:x}-{}.{}", process_id(), nanos, attempt, extension);
+        let path = env::temp_dir().join(name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists && attempt + 1 < CREATE_ATTEMPTS => {
+                attempt = attempt + 1;
+            },
+            Err(e) => return Err(format!("Failed to create \"{}\": {}", path.display(), e)),
+        }
+    }
+}
+/// Writes `contents` to a temporary file with the given extension, waits for
+/// the user's editor to close and returns what was saved. The terminal must
+/// already be handed back to the editor.
+pub fn edit(contents: &str, extension: &str) -> Result<String, String> {
+    let (path, mut file) = create_temp(extension)?;
+    if let Err(e) = file.write_all(contents.as_bytes()) {
+        let _ = remove_file(&path);
+        return Err(format!("Failed to write \"{}\": {}", path.display(), e));
+    }
+    drop(file);
+    let (program, args) = editor_command();
+    let status = Command::new(&program).args(args).arg(&path).status();
+    let result = match status {
+        Ok(status) if status.success() => read_to_string(&path)
+            .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e)),
+        Ok(status) => Err(format!("\"{}\" exited with {}", program, status)),
+        Err(e) => Err(format!("Failed to run \"{}\": {}", program, e)),
+    };
+    let _ = remove_file(&path);
+    result
+}
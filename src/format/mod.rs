@@ -0,0 +1,55 @@
+use {
+    crate::{ args::ListFormat, ctx::Ctx, },
+    std::{ fs::read_to_string, path::Path, },
+    todo_core::{ Container, GetPath, },
+};
+fn from_extension(path: &Path) -> Option<ListFormat> {
+    match path.extension()?.to_str()? {
+        "json" => Some(ListFormat::Json),
+        _ => None,
+    }
+}
+fn from_contents(contents: &str) -> Option<ListFormat> {
+    match contents.trim_start().chars().next() {
+        Some('{') => Some(ListFormat::Json),
+        _ => None,
+    }
+}
+/// Works out the storage format of the list at `path`. An explicit format
+/// always wins, then the file extension, then the contents of an existing
+/// file. Lists that do not exist yet default to JSON.
+pub fn detect(path: &Path, explicit: Option<ListFormat>) -> Result<ListFormat, String> {
+    if let Some(format) = explicit {
+        return Ok(format);
+    }
+    if let Some(format) = from_extension(path) {
+        return Ok(format);
+    }
+    if !path.exists() {
+        return Ok(ListFormat::Json);
+    }
+    let contents = read_to_string(path)
+        .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+    from_contents(&contents).ok_or(format!(
+        "Unable to detect the format of \"{}\", pass --format to choose one",
+        path.display(),
+    ))
+}
+pub fn load(ctx: &mut Ctx) -> Result<Container, String> {
+    match ctx.format {
+        ListFormat::Json => Container::load(ctx)
+            .map_err(|e| format!("Failed to load list: {}", e)),
+    }
+}
+pub fn create(ctx: &mut Ctx) -> Result<Container, String> {
+    match ctx.format {
+        ListFormat::Json => Container::create(ctx)
+            .map_err(|e| format!("Failed to create list: {}", e)),
+    }
+}
+pub fn save(ctx: &Ctx, container: &mut Container) -> Result<(), String> {
+    match ctx.format {
+        ListFormat::Json => container.save()
+            .map_err(|e| format!("Failed to save list \"{}\": {}", ctx.get_path().display(), e)),
+    }
+}
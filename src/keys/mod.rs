@@ -0,0 +1,311 @@
+use {
+    crate::nav::NavAction,
+    crossterm::event::{ KeyCode, KeyEvent, KeyModifiers, },
+    serde::Deserialize,
+    std::collections::BTreeMap,
+};
+/// A single key press along with its modifiers
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+impl KeyChord {
+    /// Folds shift into the key code where the terminal already does so, so
+    /// that `A` and `<S-a>` or `<S-Tab>` and BackTab compare equal.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut code = code;
+        let mut modifiers = modifiers;
+        match code {
+            KeyCode::Char(c) => {
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    code = KeyCode::Char(c.to_ascii_uppercase());
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    code = KeyCode::Char(c.to_ascii_lowercase());
+                }
+            },
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+            },
+            KeyCode::Tab => {
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    code = KeyCode::BackTab;
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+            },
+            _ => {},
+        }
+        Self { code, modifiers, }
+    }
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+    fn parse_named(name: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = name;
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            match &rest[0..1] {
+                "C" | "c" => modifiers.insert(KeyModifiers::CONTROL),
+                "A" | "a" | "M" | "m" => modifiers.insert(KeyModifiers::ALT),
+                "S" | "s" => modifiers.insert(KeyModifiers::SHIFT),
+                _ => return Err(format!("unknown modifier in \"<{}>\"", name)),
+            }
+            rest = &rest[2..];
+        }
+        let code = match rest.to_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "lt" => KeyCode::Char('<'),
+            "enter" | "cr" | "return" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "bs" | "backspace" => KeyCode::Backspace,
+            "del" | "delete" => KeyCode::Delete,
+            "ins" | "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            lower => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                        Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                        _ => return Err(format!("unknown key \"<{}>\"", name)),
+                    },
+                }
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+    /// Parses vim-style key notation: plain characters stand for themselves
+    /// and named or modified keys are written in angle brackets, so `gg`,
+    /// `<C-d>` and `<S-Tab>` are all valid sequences.
+    pub fn parse_sequence(s: &str) -> Result<Vec<Self>, String> {
+        let mut chords = Vec::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '<' {
+                let mut name = String::new();
+                let mut closed = false;
+                for n in chars.by_ref() {
+                    if n == '>' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(n);
+                }
+                if !closed {
+                    return Err(format!("unterminated \"<\" in \"{}\"", s));
+                }
+                chords.push(Self::parse_named(&name)?);
+            } else {
+                chords.push(Self::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+        }
+        if chords.is_empty() {
+            return Err("empty key sequence".to_string());
+        }
+        Ok(chords)
+    }
+    pub fn to_notation(&self) -> String {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char('<') => "lt".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "S-Tab".to_string(),
+            KeyCode::Backspace => "BS".to_string(),
+            KeyCode::Delete => "Del".to_string(),
+            KeyCode::Insert => "Ins".to_string(),
+            KeyCode::Up => "Up".to_string(),
+            KeyCode::Down => "Down".to_string(),
+            KeyCode::Left => "Left".to_string(),
+            KeyCode::Right => "Right".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PageUp".to_string(),
+            KeyCode::PageDown => "PageDown".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            _ => "?".to_string(),
+        };
+        let mut prefix = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            prefix.push_str("C-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            prefix.push_str("A-");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            prefix.push_str("S-");
+        }
+        let is_plain_char = match self.code {
+            KeyCode::Char(c) => c != ' ' && c != '<',
+            _ => false,
+        };
+        if prefix.is_empty() && is_plain_char {
+            name
+        } else {
+            format!("<{}{}>", prefix, name)
+        }
+    }
+}
+pub fn sequence_notation(keys: &[KeyChord]) -> String {
+    keys.iter().map(|key| key.to_notation()).collect::<Vec<String>>().join("")
+}
+/// The `[keys]` table of the config file
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyNames {
+    /// Key sequences whose default bindings are removed
+    pub unbind: Vec<String>,
+    /// Extra key sequences per action name
+    pub bind: BTreeMap<String, Vec<String>>,
+}
+pub enum Lookup {
+    Action(NavAction),
+    Pending,
+    NoMatch,
+}
+#[derive(Clone)]
+pub struct Keymap {
+    pub bindings: Vec<(Vec<KeyChord>, NavAction)>,
+}
+impl Keymap {
+    const DEFAULTS: &'static [(&'static str, NavAction)] = &[
+        ("D", NavAction::ToggleDebug),
+        ("p", NavAction::ToggleDetail),
+        ("h", NavAction::MoveOut),
+        ("j", NavAction::Next),
+        ("k", NavAction::Prev),
+        ("l", NavAction::MoveIn),
+        ("q", NavAction::Exit),
+        ("H", NavAction::ToggleShowHidden),
+        ("c", NavAction::CycleItemStatus),
+        ("x", NavAction::MarkComplete),
+        ("X", NavAction::MarkIncomplete),
+        ("-", NavAction::MarkDisabled),
+        ("s", NavAction::ToggleItemHidden),
+        ("t", NavAction::ToggleItemType),
+        ("a", NavAction::PreAddItem),
+        ("A", NavAction::PreAddRootItem),
+        ("o", NavAction::PreInsertBelow),
+        ("O", NavAction::PreInsertAbove),
+        ("I", NavAction::PreRapidEntry),
+        ("e", NavAction::EditItem),
+        ("E", NavAction::EditSubtree),
+        ("R", NavAction::RemoveItem),
+        ("<Del>", NavAction::RemoveItem),
+        ("g", NavAction::GoToTop),
+        ("G", NavAction::GoToBottom),
+        //("$", NavAction::GoToInnerLevel),
+        ("0", NavAction::GoToRootLevel),
+        ("z", NavAction::ArchiveItem),
+        ("Z", NavAction::ArchiveCompleted),
+        ("C", NavAction::ClearCompleted),
+        ("<A-c>", NavAction::ClearCompletedBelow),
+        ("u", NavAction::Undo),
+        ("V", NavAction::ToggleVisual),
+        ("<Space>", NavAction::ToggleMark),
+        ("<Esc>", NavAction::ClearSelection),
+        (">", NavAction::IndentItems),
+        ("<lt>", NavAction::OutdentItems),
+        ("K", NavAction::MoveItemsUp),
+        ("J", NavAction::MoveItemsDown),
+        ("?", NavAction::ToggleHelp),
+    ];
+    pub fn defaults() -> Self {
+        let bindings = Self::DEFAULTS.iter()
+            .map(|(keys, action)| (KeyChord::parse_sequence(keys).unwrap(), *action))
+            .collect();
+        Self { bindings, }
+    }
+    fn check_conflicts(&self) -> Result<(), String> {
+        for (i, (keys, action)) in self.bindings.iter().enumerate() {
+            for (other_keys, other_action) in self.bindings.iter().skip(i + 1) {
+                if keys.eq(other_keys) {
+                    return Err(format!(
+                        "\"{}\" is bound to both {} and {}",
+                        sequence_notation(keys), action.name(), other_action.name(),
+                    ));
+                }
+                let (short, short_action, long, long_action) = if keys.len() < other_keys.len() {
+                    (keys, action, other_keys, other_action)
+                } else {
+                    (other_keys, other_action, keys, action)
+                };
+                if long.starts_with(short) {
+                    return Err(format!(
+                        "\"{}\" ({}) is a prefix of \"{}\" ({}), unbind one of them",
+                        sequence_notation(short), short_action.name(),
+                        sequence_notation(long), long_action.name(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Applies the config's unbinds and binds on top of the defaults and
+    /// rejects ambiguous results.
+    pub fn from_config(names: &KeyNames) -> Result<Self, String> {
+        let mut keymap = Self::defaults();
+        for keys in names.unbind.iter() {
+            let chords = KeyChord::parse_sequence(keys)?;
+            let before = keymap.bindings.len();
+            keymap.bindings.retain(|(bound, _)| !bound.eq(&chords));
+            if keymap.bindings.len() == before {
+                return Err(format!("cannot unbind \"{}\", nothing is bound to it", keys));
+            }
+        }
+        for (name, sequences) in names.bind.iter() {
+            let action = NavAction::from_name(name)
+                .ok_or(format!("unknown action \"{}\"", name))?;
+            for keys in sequences.iter() {
+                let chords = KeyChord::parse_sequence(keys)?;
+                let exists = keymap.bindings.iter()
+                    .any(|(bound, bound_action)| bound.eq(&chords) && bound_action.eq(&action));
+                if !exists {
+                    keymap.bindings.push((chords, action));
+                }
+            }
+        }
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+    pub fn lookup(&self, keys: &[KeyChord]) -> Lookup {
+        let mut pending = false;
+        for (bound, action) in self.bindings.iter() {
+            if bound.as_slice().eq(keys) {
+                return Lookup::Action(*action);
+            }
+            if bound.starts_with(keys) {
+                pending = true;
+            }
+        }
+        if pending {
+            Lookup::Pending
+        } else {
+            Lookup::NoMatch
+        }
+    }
+    /// All key sequences bound to `action`, in binding order
+    pub fn keys_for(&self, action: NavAction) -> Vec<String> {
+        self.bindings.iter()
+            .filter(|(_, bound_action)| bound_action.eq(&action))
+            .map(|(keys, _)| sequence_notation(keys))
+            .collect()
+    }
+}
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::defaults()
+    }
+}
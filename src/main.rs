@@ -1,63 +1,106 @@
+mod archive;
 mod args;
+mod config;
+mod conv;
 mod ctx;
+mod dirs;
+mod editor;
+mod format;
+mod keys;
 mod log;
 mod nav;
+mod outline;
 mod term;
+mod theme;
+mod tree;
 mod win;
+mod wrap;
 use {
     args::{ Args, Mode },
     clap::Parser,
+    config::Config,
     crossterm::event,
     ctx::Ctx,
+    nav::Navigator,
     std::{
+        fmt::Display,
         io::{ Error as IOError, stdout as get_stdout, },
-        thread::spawn as thread_spawn,
+        process::exit,
+        sync::Arc,
+        thread::{ sleep, spawn as thread_spawn, },
         time::{ Duration, Instant, },
         sync::mpsc::channel,
     },
-    term::{ TermEvent, TerminalManager, },
-    todo_core::Container,
+    term::{ InputGate, TermEvent, TerminalManager, },
+    todo_core::GetPath,
 };
-trait PrintCoords {
-    fn to_coords(&self) -> String;
-}
-impl PrintCoords for Vec<usize> {
-    fn to_coords(&self) -> String {
-        let mut out = String::new();
-        let mut delim = "";
-        for item in self {
-            out.push_str(&format!("{}{}", delim, item));
-            delim = ",";
-        }
-        out
-    }
+/// Reports a user-facing error and quits, without a panic message.
+fn fail(e: impl Display) -> ! {
+    eprintln!("{}", e);
+    exit(1);
 }
 fn main() -> Result<(), IOError> {
-    const TICK: u64 = 200;
-    let tick_rate = Duration::from_millis(TICK);
     let mut ctx;
     { // construct ctx
         let args = Args::parse();
-        ctx = Ctx::new(args);
+        let config = Config::load(args.config.as_deref()).unwrap_or_else(|e| fail(e));
+        ctx = Ctx::new(args, config);
     }
+    let tick_rate = Duration::from_millis(ctx.config.tick);
     ctx.construct_path();
+    ctx.detect_format().unwrap_or_else(|e| fail(e));
     match ctx.args.mode.clone() {
-        Mode::New(_) => {
-            let mut c = Container::create(&mut ctx).unwrap_or_else(|e| panic!("{}", e));
-            c.save().unwrap_or_else(|e| panic!("{}", e));
+        Some(Mode::Import(args)) => {
+            let count = conv::import(&mut ctx, &args).unwrap_or_else(|e| fail(e));
+            println!("Imported {} items into \"{}\"", count, ctx.get_path().to_str().unwrap());
+            return Ok(());
+        },
+        Some(Mode::Export(args)) => {
+            conv::export(&mut ctx, &args).unwrap_or_else(|e| fail(e));
+            return Ok(());
+        },
+        Some(Mode::Archive(_)) => if !ctx.get_path().exists() {
+            fail(format!("No archive found at \"{}\"", ctx.get_path().display()));
         },
-        Mode::Open(_) => {},
+        _ => {},
+    }
+    let mut created_dir = None;
+    if ctx.should_create() {
+        created_dir = ctx.create_parent_dir().unwrap_or_else(|e| fail(format!(
+            "Failed to create the directory of \"{}\": {}", ctx.get_path().display(), e,
+        )));
+        let mut c = format::create(&mut ctx).unwrap_or_else(|e| fail(e));
+        format::save(&ctx, &mut c).unwrap_or_else(|e| fail(e));
     }
     // main vars
     let (tx, rx) = channel();
+    // paused while an external editor owns the terminal
+    let input = Arc::new(InputGate::default());
+    let thread_input = input.clone();
     thread_spawn(move || {
         let mut last_tick = Instant::now();
         loop {
+            if thread_input.is_paused() {
+                thread_input.acknowledge();
+                sleep(tick_rate);
+                continue;
+            }
             let timeout = tick_rate.checked_sub(last_tick.elapsed())
                 .unwrap_or_else(|| Duration::from_secs(0));
             if event::poll(timeout).unwrap() {
-                if let event::Event::Key(key) = event::read().unwrap() {
-                    tx.send(TermEvent::Key(key)).unwrap();
+                // a pause may have come in while polling, leave the input
+                // to whoever it is for
+                if thread_input.is_paused() {
+                    continue;
+                }
+                match event::read().unwrap() {
+                    event::Event::Key(key) if key.kind == event::KeyEventKind::Press => {
+                        tx.send(TermEvent::Key(key)).unwrap();
+                    },
+                    event::Event::Paste(text) => {
+                        tx.send(TermEvent::Paste(text)).unwrap();
+                    },
+                    _ => {},
                 }
             }
             if last_tick.elapsed() >= tick_rate {
@@ -67,7 +110,12 @@ fn main() -> Result<(), IOError> {
             }
         }
     });
-    let mut tman = TerminalManager::init(&mut ctx, get_stdout(), rx)?;
+    // load before taking over the terminal so that errors are readable
+    let mut navigator = Navigator::new(&mut ctx).unwrap_or_else(|e| fail(e));
+    if let Some(dir) = created_dir {
+        navigator.push_status(format!("Created directory \"{}\"", dir.display()));
+    }
+    let mut tman = TerminalManager::init(navigator, get_stdout(), rx, input)?;
     tman.run(&mut ctx);
     tman.exit()?;
     Ok(())
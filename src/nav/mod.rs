@@ -1,9 +1,10 @@
 use {
     crate::{
-        ctx::Ctx, log::{ LogMsg, LogType }, term::TermEvent,
-        win::WindowBufferBounds,
+        archive, config::{ Config, ProgressStyle, }, ctx::Ctx, format,
+        keys::{ KeyChord, Lookup, }, log::{ LogMsg, LogType }, term::TermEvent,
+        outline, tree::{ self, ItemVisitor, PrintCoords, }, win::WindowBufferBounds, wrap,
     },
-    crossterm::event::KeyCode,
+    crossterm::event::{ KeyCode, KeyModifiers, },
     md5::{Md5, Digest},
     std::{
         fs::File,
@@ -27,8 +28,10 @@ pub trait NavigateMap {
 pub enum NavMode {
     Navigate,
     Input,
+    Help,
+    Confirm,
 }
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum NavAction {
     MoveOut,
     Next,
@@ -50,36 +53,163 @@ pub enum NavAction {
     GoToTop,
     GoToRootLevel,
     //GoToInnerLevel,
+    ToggleHelp,
+    CompleteDescendants,
+    RemoveItemConfirmed,
+    ArchiveItem,
+    ArchiveCompleted,
+    ArchiveCompletedConfirmed,
+    ClearCompleted,
+    ClearCompletedBelow,
+    ClearCompletedRemove,
+    ClearCompletedHide,
+    Undo,
+    ToggleVisual,
+    ToggleMark,
+    ClearSelection,
+    IndentItems,
+    OutdentItems,
+    MoveItemsUp,
+    MoveItemsDown,
+    MarkComplete,
+    MarkIncomplete,
+    MarkDisabled,
+    PreInsertBelow,
+    PreInsertAbove,
+    InsertItem,
+    CancelInsert,
+    PreRapidEntry,
+    FinishRapidEntry,
+    PasteItems,
+    EditItem,
+    EditSubtree,
+    ToggleDetail,
 }
-impl NavAction {
-    pub fn from_event(event: TermEvent) -> Self {
-        match event {
-            TermEvent::Key(key) => {
-                match key.code {
-                    KeyCode::Char('D') => Self::ToggleDebug,
-                    KeyCode::Char('h') => Self::MoveOut,
-                    KeyCode::Char('j') => Self::Next,
-                    KeyCode::Char('k') => Self::Prev,
-                    KeyCode::Char('l') => Self::MoveIn,
-                    KeyCode::Char('q') => Self::Exit,
-                    KeyCode::Char('H') => Self::ToggleShowHidden,
-                    KeyCode::Char('c') => Self::CycleItemStatus,
-                    KeyCode::Char('s') => Self::ToggleItemHidden,
-                    KeyCode::Char('t') => Self::ToggleItemType,
-                    KeyCode::Char('a') => Self::PreAddItem,
-                    KeyCode::Char('A') => Self::PreAddRootItem,
-                    KeyCode::Char('R') | KeyCode::Delete => Self::RemoveItem,
-                    KeyCode::Char('g') => Self::GoToTop,
-                    KeyCode::Char('G') => Self::GoToBottom,
-                    //KeyCode::Char('$') => Self::GoToInnerLevel,
-                    KeyCode::Char('0') => Self::GoToRootLevel,
-                    _ => Self::NoAction,
-                }
-            },
-            TermEvent::Tick => Self::NoAction,
+#[derive(Clone, Copy, PartialEq)]
+pub enum ActionCategory {
+    Movement,
+    Selection,
+    Editing,
+    View,
+    File,
+}
+impl ActionCategory {
+    pub const ALL: [ActionCategory; 5] = [
+        ActionCategory::Movement, ActionCategory::Selection, ActionCategory::Editing,
+        ActionCategory::View, ActionCategory::File,
+    ];
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Movement => "Movement",
+            Self::Selection => "Selection",
+            Self::Editing => "Editing",
+            Self::View => "View",
+            Self::File => "File",
         }
     }
 }
+impl NavAction {
+    /// Actions that can be bound to keys, with their config names, help
+    /// category and help text
+    pub const BINDABLE: &'static [(NavAction, &'static str, ActionCategory, &'static str)] = &[
+        (NavAction::MoveOut, "move_out", ActionCategory::Movement, "Move out to the parent item"),
+        (NavAction::Next, "next", ActionCategory::Movement, "Next item"),
+        (NavAction::Prev, "prev", ActionCategory::Movement, "Previous item"),
+        (NavAction::MoveIn, "move_in", ActionCategory::Movement, "Move in to the first child"),
+        (NavAction::GoToTop, "go_to_top", ActionCategory::Movement, "First item of this level"),
+        (NavAction::GoToBottom, "go_to_bottom", ActionCategory::Movement, "Last item of this level"),
+        (NavAction::GoToRootLevel, "go_to_root_level", ActionCategory::Movement, "Root of this branch"),
+        (NavAction::ToggleVisual, "toggle_visual", ActionCategory::Selection, "Start or end a range selection"),
+        (NavAction::ToggleMark, "toggle_mark", ActionCategory::Selection, "Mark or unmark item"),
+        (NavAction::ClearSelection, "clear_selection", ActionCategory::Selection, "Clear the selection"),
+        (NavAction::CycleItemStatus, "cycle_item_status", ActionCategory::Editing, "Cycle status"),
+        (NavAction::MarkComplete, "mark_complete", ActionCategory::Editing, "Mark complete"),
+        (NavAction::MarkIncomplete, "mark_incomplete", ActionCategory::Editing, "Mark incomplete"),
+        (NavAction::MarkDisabled, "mark_disabled", ActionCategory::Editing, "Mark disabled"),
+        (NavAction::ToggleItemHidden, "toggle_item_hidden", ActionCategory::Editing, "Hide or unhide item"),
+        (NavAction::ToggleItemType, "toggle_item_type", ActionCategory::Editing, "Toggle todo or note"),
+        (NavAction::IndentItems, "indent_items", ActionCategory::Editing, "Indent under the previous item"),
+        (NavAction::OutdentItems, "outdent_items", ActionCategory::Editing, "Outdent to after the parent"),
+        (NavAction::MoveItemsUp, "move_items_up", ActionCategory::Editing, "Move up past the previous item"),
+        (NavAction::MoveItemsDown, "move_items_down", ActionCategory::Editing, "Move down past the next item"),
+        (NavAction::PreAddItem, "add_item", ActionCategory::Editing, "Add child item"),
+        (NavAction::PreAddRootItem, "add_root_item", ActionCategory::Editing, "Add root item"),
+        (NavAction::PreInsertBelow, "insert_below", ActionCategory::Editing, "Insert sibling below"),
+        (NavAction::PreInsertAbove, "insert_above", ActionCategory::Editing, "Insert sibling above"),
+        (NavAction::PreRapidEntry, "rapid_entry", ActionCategory::Editing, "Add several siblings in a row"),
+        (NavAction::EditItem, "edit_item", ActionCategory::Editing, "Edit text in $EDITOR"),
+        (NavAction::EditSubtree, "edit_subtree", ActionCategory::Editing, "Edit item and children in $EDITOR"),
+        (NavAction::RemoveItem, "remove_item", ActionCategory::Editing, "Remove item and children"),
+        (NavAction::ArchiveItem, "archive_item", ActionCategory::Editing, "Archive completed item"),
+        (NavAction::ArchiveCompleted, "archive_completed", ActionCategory::Editing, "Archive completed items below"),
+        (NavAction::ClearCompleted, "clear_completed", ActionCategory::Editing, "Clear completed items in the list"),
+        (NavAction::ClearCompletedBelow, "clear_completed_below", ActionCategory::Editing, "Clear completed items below"),
+        (NavAction::Undo, "undo", ActionCategory::Editing, "Undo the last change"),
+        (NavAction::ToggleShowHidden, "toggle_show_hidden", ActionCategory::View, "Show or hide hidden items"),
+        (NavAction::ToggleDetail, "toggle_detail", ActionCategory::View, "Toggle detail pane"),
+        (NavAction::ToggleDebug, "toggle_debug", ActionCategory::View, "Toggle debug panel"),
+        (NavAction::ToggleHelp, "toggle_help", ActionCategory::View, "Toggle this help"),
+        (NavAction::Exit, "exit", ActionCategory::File, "Quit"),
+    ];
+    pub fn name(&self) -> &'static str {
+        Self::BINDABLE.iter()
+            .find(|(action, _, _, _)| action.eq(self))
+            .map(|(_, name, _, _)| *name)
+            .unwrap_or("no_action")
+    }
+    /// Whether the action changes the list
+    pub fn modifies(&self) -> bool {
+        matches!(
+            self,
+            Self::CycleItemStatus | Self::ToggleItemHidden | Self::ToggleItemType
+                | Self::PreAddItem | Self::AddItem | Self::PreAddRootItem
+                | Self::AddRootItem | Self::RemoveItem | Self::RemoveItemConfirmed
+                | Self::CompleteDescendants | Self::ArchiveItem
+                | Self::ArchiveCompleted | Self::ArchiveCompletedConfirmed
+                | Self::ClearCompleted | Self::ClearCompletedBelow
+                | Self::ClearCompletedRemove | Self::ClearCompletedHide | Self::Undo
+                | Self::IndentItems | Self::OutdentItems | Self::MoveItemsUp
+                | Self::MoveItemsDown | Self::MarkComplete | Self::MarkIncomplete
+                | Self::MarkDisabled | Self::PreInsertBelow | Self::PreInsertAbove
+                | Self::InsertItem | Self::CancelInsert | Self::PreRapidEntry
+                | Self::FinishRapidEntry | Self::PasteItems | Self::EditItem
+                | Self::EditSubtree
+        )
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::BINDABLE.iter()
+            .find(|(_, action_name, _, _)| action_name.eq(&name))
+            .map(|(action, _, _, _)| *action)
+    }
+}
+/// A question waiting on an answer. Each choice pairs a key with a label and
+/// the action it takes; any other key cancels.
+pub struct Confirm {
+    pub prompt: String,
+    pub choices: Vec<(char, &'static str, NavAction)>,
+}
+impl Confirm {
+    pub fn hint(&self) -> String {
+        let mut hint = self.choices.iter()
+            .map(|(key, label, _)| format!("[{}] {}", key, label))
+            .collect::<Vec<String>>();
+        hint.push("[N] cancel".to_string());
+        hint.join("  ")
+    }
+}
+/// An item to open in the external editor, alone or with its descendants
+pub struct EditRequest {
+    pub pos: Vec<usize>,
+    pub subtree: bool,
+}
+/// A change that can be undone: the list, and the archive when items were
+/// archived, as they were before it
+struct UndoStep {
+    list: Container,
+    /// Hash of the list file right after the change
+    hash: String,
+    archive: Option<archive::Snapshot>,
+}
 pub struct NavigationMap {
     position: Vec<usize>,
     valid_positions: Vec<Vec<usize>>,
@@ -87,17 +217,49 @@ pub struct NavigationMap {
 }
 pub struct Navigator {
     pub height: u16,
+    pub width: u16,
     map: NavigationMap,
     d_buffer: Vec<LogMsg>,
     display_hidden: bool,
     pub debug: bool,
+    pub detail: bool,
     action: NavAction,
     pub mode: NavMode,
     container: Container,
     pub file_hash: String,
     pub i_buffer: String,
+    config: Config,
+    pending_keys: Vec<KeyChord>,
+    pub help_scroll: u16,
+    dirty: bool,
+    status_msg: Option<(String, u16)>,
+    pub confirm: Option<Confirm>,
+    read_only: bool,
+    /// Changes that can be undone, most recent last
+    history: Vec<UndoStep>,
+    /// Parent whose completed items are being cleared, empty for the list
+    clear_scope: Vec<usize>,
+    /// Where the range selection started, while one is being made
+    visual_anchor: Option<Vec<usize>>,
+    /// Items marked one by one
+    marked: Vec<Vec<usize>>,
+    /// Whether the item being typed is a note rather than a todo
+    input_note: bool,
+    /// Where the cursor was before an unsaved row was inserted for typing
+    insert_origin: Option<Vec<usize>>,
+    /// Items added so far and the position of the latest one, while in
+    /// rapid entry
+    rapid_entry: Option<(usize, Vec<usize>)>,
+    /// Pasted text waiting to be added, with the parent and index it goes to
+    paste: Option<(String, Vec<usize>, usize)>,
+    /// Set when the terminal should be handed to the external editor
+    pub edit: Option<EditRequest>,
 }
 impl Navigator {
+    /// Number of ticks a status bar message stays visible
+    const STATUS_MSG_TICKS: u16 = 15;
+    /// Number of saves that can be undone
+    const HISTORY_LIMIT: usize = 50;
     pub fn get_file_hash(ctx: &mut Ctx) -> String {
         let path = ctx.get_path();
         if !path.exists() {
@@ -113,10 +275,9 @@ impl Navigator {
         let result = hasher.finalize();
         format!("{:x}", result)
     }
-    pub fn new(ctx: &mut Ctx) -> Self {
-        let display_hidden = ctx.args.display_hidden;
-        let container = Container::load(ctx)
-            .unwrap_or_else(|e| panic!("Failed to load list: {}", e));
+    pub fn new(ctx: &mut Ctx) -> Result<Self, String> {
+        let display_hidden = ctx.args.display_hidden_or(ctx.config.display_hidden);
+        let container = format::load(ctx)?;
         let hash = Self::get_file_hash(ctx);
         let valid_pos = Self::from_items(&container.list.items, display_hidden);
         let nav_map = NavigationMap {
@@ -124,39 +285,547 @@ impl Navigator {
             position: vec![0],
             window_buffer: WindowBufferBounds::init(),
         };
-        Self {
+        Ok(Self {
             height: 0,
-            debug: ctx.args.debug, d_buffer: Vec::new(), display_hidden, map: nav_map,
+            width: 0,
+            debug: ctx.args.debug_or(ctx.config.debug), d_buffer: Vec::new(), display_hidden, map: nav_map,
+            detail: ctx.config.detail_pane,
             action: NavAction::NoAction, container, mode: NavMode::Navigate,
             i_buffer: String::new(),
             file_hash: hash,
-        }
+            config: ctx.config.clone(),
+            pending_keys: Vec::new(),
+            help_scroll: 0,
+            dirty: false,
+            status_msg: None,
+            confirm: None,
+            read_only: ctx.read_only(),
+            history: Vec::new(),
+            clear_scope: Vec::new(),
+            visual_anchor: None,
+            marked: Vec::new(),
+            input_note: false,
+            insert_origin: None,
+            rapid_entry: None,
+            paste: None,
+            edit: None,
+        })
     }
     pub fn reload(&mut self, ctx: &mut Ctx) {
-        let container = match Container::load(ctx) {
+        let container = match format::load(ctx) {
             Ok(c) => c,
             Err(e) => {
-                self.push_error(format!("Failed to load list: {}", e));
+                self.push_error(e);
                 return;
             },
         };
-        let nav_map = Self::from_items(&container.list.items, self.display_hidden);
         self.container = container;
-        self.map.valid_positions = nav_map;
-        self.set_nearest_pos();
+        self.dirty = false;
+        self.rebuild_map();
         self.file_hash = Self::get_file_hash(ctx);
         self.push_log(
             format!("Hash reloaded {}", self.file_hash)
         );
     }
-    pub fn save_and_reload(&mut self, ctx: &mut Ctx) {
-        match self.container.save() {
-            Ok(_) => {},
+    /// Rebuilds the navigation map from the items in memory.
+    fn rebuild_map(&mut self) {
+        self.map.valid_positions = Self::from_items(&self.container.list.items, self.display_hidden);
+        // positions no longer point at the same items
+        self.clear_selection();
+        self.set_nearest_pos();
+    }
+    /// Saves the list and reloads it from disk. When saving fails the changes
+    /// are kept in memory, the error is shown and `false` is returned.
+    pub fn save_and_reload(&mut self, ctx: &mut Ctx) -> bool {
+        self.save_and_record(ctx, None).is_ok()
+    }
+    /// Like `save_and_reload`, with `archive` holding the archive from before
+    /// the change so that undo puts both files back. It is handed back when
+    /// saving fails.
+    fn save_and_record(
+        &mut self, ctx: &mut Ctx, archive: Option<archive::Snapshot>,
+    ) -> Result<(), Option<archive::Snapshot>> {
+        let before_hash = Self::get_file_hash(ctx);
+        let before = format::load(ctx).ok();
+        match format::save(ctx, &mut self.container) {
+            Ok(_) => {
+                self.dirty = false;
+                let hash = Self::get_file_hash(ctx);
+                if let Some(list) = before {
+                    if !before_hash.eq(&hash) {
+                        self.history.push(UndoStep { list, hash, archive, });
+                        if self.history.len() > Self::HISTORY_LIMIT {
+                            self.history.remove(0);
+                        }
+                    }
+                }
+            },
             Err(e) => {
-                self.push_error(format!("Failed to save list: {}", e));
+                self.dirty = true;
+                self.push_status("Failed to save list");
+                self.push_error(e);
+                self.rebuild_map();
+                return Err(archive);
             },
         }
         self.reload(ctx);
+        Ok(())
+    }
+    /// Positions of the items in the range selection and the marked items, in
+    /// display order. Empty when nothing is selected.
+    fn selected_positions(&self) -> Vec<Vec<usize>> {
+        let valid = &self.map.valid_positions;
+        let range = self.visual_anchor.as_ref()
+            .and_then(|anchor| valid.iter().position(|pos| pos.eq(anchor)))
+            .and_then(|anchor| {
+                valid.iter().position(|pos| pos.eq(&self.map.position))
+                    .map(|cursor| (anchor.min(cursor), anchor.max(cursor)))
+            });
+        valid.iter().enumerate()
+            .filter(|(i, pos)| {
+                range.map(|(start, end)| *i >= start && *i <= end).unwrap_or(false)
+                    || self.marked.contains(pos)
+            })
+            .map(|(_, pos)| pos.clone())
+            .collect()
+    }
+    /// The items an editing action applies to: the selection, or the item
+    /// under the cursor when there is none.
+    fn selection(&self) -> Vec<Vec<usize>> {
+        let selected = self.selected_positions();
+        if selected.is_empty() {
+            vec![self.map.position.clone()]
+        } else {
+            selected
+        }
+    }
+    fn clear_selection(&mut self) {
+        self.visual_anchor = None;
+        self.marked.clear();
+    }
+    /// Applies `action` to every selected item and returns how many there
+    /// were.
+    fn act_on_selection(&mut self, action: fn() -> ItemAction) -> usize {
+        let positions = self.selection();
+        for pos in positions.iter() {
+            self.container.act_on_item_at(&mut tree::to_location(pos), action());
+        }
+        positions.len()
+    }
+    /// Sets the status of every selected todo, leaving notes alone, and
+    /// returns how many todos there were.
+    fn set_status(&mut self, action: NavAction) -> usize {
+        let positions = self.selection();
+        let mut count = 0;
+        for pos in positions.iter() {
+            if let Some(item) = tree::item_at_mut(&mut self.container.list.items, pos) {
+                if let ItemType::Note = item.item_type {
+                    continue;
+                }
+                item.status = match action {
+                    NavAction::MarkComplete => ItemStatus::Complete,
+                    NavAction::MarkDisabled => ItemStatus::Disabled,
+                    _ => ItemStatus::Incomplete,
+                };
+                count = count + 1;
+            }
+        }
+        if self.config.auto_complete_parents {
+            for pos in positions.iter() {
+                self.propagate_status(pos);
+            }
+        }
+        count
+    }
+    fn input_type(&self) -> ItemType {
+        if self.input_note {
+            ItemType::Note
+        } else {
+            ItemType::Todo
+        }
+    }
+    /// Title of the text box, naming the type of the item being typed
+    pub fn input_title(&self) -> String {
+        let (item_type, other) = if self.input_note { ("note", "todo") } else { ("todo", "note") };
+        match &self.rapid_entry {
+            Some((count, _)) => format!(
+                "New {} #{} (Ctrl-t for {}, Tab/S-Tab to indent, Esc to finish)",
+                item_type, count + 1, other,
+            ),
+            None => format!("New {} (Ctrl-t for {})", item_type, other),
+        }
+    }
+    /// Title of the help overlay, naming the keys that scroll and close it.
+    pub fn help_title(&self) -> String {
+        let keymap = &self.config.keymap;
+        let mut close = vec![ "Esc".to_string(), ];
+        close.extend(keymap.keys_for(NavAction::ToggleHelp));
+        let mut hints = vec![ format!("{} to close", close.join(" or ")), ];
+        let next = keymap.keys_for(NavAction::Next);
+        let prev = keymap.keys_for(NavAction::Prev);
+        if !next.is_empty() && !prev.is_empty() {
+            hints.push(format!("{}/{} to scroll", next.join(","), prev.join(",")));
+        }
+        format!("Help ({})", hints.join(", "))
+    }
+    /// Inserts an empty sibling next to the cursor without saving it and
+    /// moves the cursor onto it, so that it can be typed in place.
+    fn begin_insert(&mut self, below: bool) {
+        let pos = self.map.position.clone();
+        let (index, parent) = match pos.split_last() {
+            Some((index, parent)) => (*index, parent.to_vec()),
+            None => (0, Vec::new()),
+        };
+        let index = if below && !self.map.valid_positions.is_empty() { index + 1 } else { index };
+        match tree::insert_item(&mut self.container, &parent, index, self.input_type(), "") {
+            Some(new_pos) => {
+                if self.insert_origin.is_none() {
+                    self.insert_origin = Some(pos);
+                }
+                self.map.valid_positions = Self::from_items(
+                    &self.container.list.items, self.display_hidden,
+                );
+                self.map.position = new_pos;
+                self.mode = NavMode::Input;
+                // the empty row stays unsaved until the insert is finished
+                self.dirty = true;
+            },
+            None => {
+                self.push_status("Failed to insert item");
+            },
+        }
+    }
+    /// Keeps the typed row in rapid entry and opens an empty one below it,
+    /// without saving.
+    fn commit_rapid_entry(&mut self) {
+        let pos = self.map.position.clone();
+        let item_type = self.input_type();
+        if let Some(item) = tree::item_at_mut(&mut self.container.list.items, &pos) {
+            item.text = self.i_buffer.clone();
+            item.item_type = item_type;
+        }
+        self.i_buffer = String::new();
+        let count = self.rapid_entry.as_ref().map(|(count, _)| *count).unwrap_or(0);
+        self.rapid_entry = Some((count + 1, pos));
+        self.begin_insert(true);
+    }
+    /// Indents or outdents the row being typed in rapid entry.
+    fn shift_rapid_entry(&mut self, indent: bool) {
+        let positions = vec![ self.map.position.clone(), ];
+        let shifted = if indent {
+            tree::indent_items(&mut self.container.list.items, &positions)
+        } else {
+            tree::outdent_items(&mut self.container.list.items, &positions)
+        };
+        match shifted {
+            Ok(pos) => {
+                self.map.valid_positions = Self::from_items(
+                    &self.container.list.items, self.display_hidden,
+                );
+                self.map.position = pos;
+            },
+            Err(e) => {
+                self.push_status(e);
+            },
+        }
+    }
+    /// Drops the empty row left at the end of rapid entry and saves
+    /// everything typed in one go.
+    fn finish_rapid_entry(&mut self, ctx: &mut Ctx) {
+        let pos = self.map.position.clone();
+        let origin = self.insert_origin.take();
+        self.input_note = false;
+        match self.rapid_entry.take() {
+            Some((count, last)) if count > 0 => {
+                tree::remove_items(&mut self.container.list.items, &vec![ pos, ]);
+                self.map.position = last;
+                if self.save_and_reload(ctx) {
+                    self.push_status(format!("Added {} items", count));
+                }
+            },
+            _ => {
+                if let Some(origin) = origin {
+                    self.map.position = origin;
+                }
+                self.reload(ctx);
+            },
+        }
+    }
+    /// Queues pasted text to be added as items. A paste while navigating
+    /// goes below the cursor; one while typing replaces the item being typed,
+    /// unless it is a single line, which is typed as is.
+    fn handle_paste(&mut self, text: String) {
+        match self.mode {
+            NavMode::Navigate => {
+                let (index, parent) = match self.map.position.split_last() {
+                    Some(_) if self.map.valid_positions.is_empty() => (0, Vec::new()),
+                    Some((index, parent)) => (index + 1, parent.to_vec()),
+                    None => (0, Vec::new()),
+                };
+                self.paste = Some((text, parent, index));
+                self.action = NavAction::PasteItems;
+            },
+            NavMode::Input => {
+                let trimmed = text.trim_end_matches(|c| c == '\r' || c == '\n');
+                if !trimmed.contains('\n') {
+                    self.i_buffer.push_str(trimmed);
+                    return;
+                }
+                let text = format!("{}{}", self.i_buffer, text);
+                let (parent, index) = match self.action {
+                    // with nothing to add below, a child paste goes to the root
+                    NavAction::PreAddItem if self.map.valid_positions.is_empty() => {
+                        (Vec::new(), usize::MAX)
+                    },
+                    NavAction::PreAddItem => (self.map.position.clone(), usize::MAX),
+                    NavAction::PreAddRootItem => (Vec::new(), usize::MAX),
+                    _ if self.insert_origin.is_some() => {
+                        // the pasted items take the place of the unsaved row
+                        let pos = self.map.position.clone();
+                        tree::remove_items(&mut self.container.list.items, &vec![ pos.clone(), ]);
+                        match pos.split_last() {
+                            Some((index, parent)) => (parent.to_vec(), *index),
+                            None => (Vec::new(), 0),
+                        }
+                    },
+                    _ => return,
+                };
+                self.i_buffer = String::new();
+                self.input_note = false;
+                self.mode = NavMode::Navigate;
+                self.paste = Some((text, parent, index));
+                self.action = NavAction::PasteItems;
+            },
+            NavMode::Help | NavMode::Confirm => {},
+        }
+    }
+    fn paste_items(&mut self, ctx: &mut Ctx) {
+        let (text, parent, index) = match self.paste.take() {
+            Some(paste) => paste,
+            None => return,
+        };
+        self.insert_origin = None;
+        self.rapid_entry = None;
+        match outline::insert(&mut self.container, &parent, index, outline::parse(&text, false)) {
+            Ok(added) => {
+                if let Some(first) = added.first() {
+                    self.map.position = first.clone();
+                }
+                if self.save_and_reload(ctx) {
+                    self.push_status(format!("Pasted {} items", added.len()));
+                }
+            },
+            Err(e) => {
+                self.reload(ctx);
+                self.push_status("Failed to paste");
+                self.push_error(e);
+            },
+        }
+    }
+    /// The file contents to open in the editor and their extension
+    pub fn edit_contents(&self, edit: &EditRequest) -> Option<(String, &'static str)> {
+        let item = tree::item_at(&self.container.list.items, &edit.pos)?;
+        if edit.subtree {
+            Some((outline::write(item), "md"))
+        } else {
+            Some((format!("{}\n", item.text), "txt"))
+        }
+    }
+    /// Puts the text saved in the editor back into the list. An empty file,
+    /// or an outline without a single item, leaves the list untouched.
+    pub fn apply_edit(&mut self, ctx: &mut Ctx, edit: EditRequest, result: Result<String, String>) {
+        let contents = match result {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.push_status("Edit failed");
+                self.push_error(e);
+                return;
+            },
+        };
+        if contents.trim().is_empty() {
+            self.push_status("Edit discarded, the file was empty");
+            return;
+        }
+        if !edit.subtree {
+            // only the newline the editor adds at the end is not part of it
+            let text = contents.strip_suffix("\r\n")
+                .or(contents.strip_suffix('\n'))
+                .unwrap_or(&contents)
+                .to_string();
+            match tree::item_at_mut(&mut self.container.list.items, &edit.pos) {
+                Some(item) => item.text = text,
+                None => {
+                    self.push_status("The edited item no longer exists");
+                    return;
+                },
+            }
+            if self.save_and_reload(ctx) {
+                self.push_status("Item edited");
+            }
+            return;
+        }
+        let (index, parent) = match edit.pos.split_last() {
+            Some((index, parent)) => (*index, parent.to_vec()),
+            None => return,
+        };
+        let lines = outline::parse(&contents, true);
+        if lines.is_empty() {
+            self.push_status("Edit discarded, no items were found");
+            return;
+        }
+        tree::remove_items(&mut self.container.list.items, &vec![ edit.pos.clone(), ]);
+        match outline::insert(&mut self.container, &parent, index, lines) {
+            Ok(added) => {
+                self.map.position = edit.pos;
+                if self.save_and_reload(ctx) {
+                    self.push_status(format!("Replaced with {} items", added.len()));
+                }
+            },
+            Err(e) => {
+                self.reload(ctx);
+                self.push_status("Edit failed");
+                self.push_error(e);
+            },
+        }
+    }
+    /// Runs a structural change over the selection and follows the first
+    /// moved item with the cursor.
+    fn restructure(
+        &mut self, ctx: &mut Ctx,
+        change: fn(&mut Vec<Item>, &Vec<Vec<usize>>) -> Result<Vec<usize>, String>,
+        done: &str,
+    ) {
+        let positions = self.selection();
+        match change(&mut self.container.list.items, &positions) {
+            Ok(first) => {
+                self.map.position = first;
+                if self.save_and_reload(ctx) {
+                    self.push_status(done);
+                }
+            },
+            Err(e) => {
+                self.push_status(e);
+            },
+        }
+    }
+    /// Restores the file contents from before the last save.
+    pub fn undo(&mut self, ctx: &mut Ctx) {
+        let step = match self.history.pop() {
+            Some(step) => step,
+            None => {
+                self.push_status("Nothing to undo");
+                return;
+            },
+        };
+        // going back past a change made outside would silently revert it
+        if !Self::get_file_hash(ctx).eq(&step.hash) {
+            self.history.clear();
+            self.push_status("The list changed outside, nothing to undo");
+            return;
+        }
+        let mut list = step.list;
+        match format::save(ctx, &mut list) {
+            Ok(_) => {
+                // after the list, so that a failure leaves archived items in
+                // both files rather than in neither
+                if let Some(archive) = step.archive {
+                    if let Err(e) = archive.restore() {
+                        self.push_error(format!("Failed to restore the archive: {}", e));
+                    }
+                }
+                self.reload(ctx);
+                self.push_status("Undone");
+            },
+            Err(e) => {
+                self.push_status("Failed to undo");
+                self.push_error(e);
+            },
+        }
+    }
+    /// Shows a message in the status bar for a few ticks.
+    pub fn push_status(&mut self, msg: impl AsRef<str>) {
+        self.status_msg = Some((msg.as_ref().to_string(), Self::STATUS_MSG_TICKS));
+    }
+    fn tick_status(&mut self) {
+        let expired = match &mut self.status_msg {
+            Some((_, ticks)) => {
+                *ticks = ticks.saturating_sub(1);
+                *ticks == 0
+            },
+            None => false,
+        };
+        if expired {
+            self.status_msg = None;
+        }
+    }
+    /// Formats the progress of the todos among `items`, or nothing when there
+    /// are none or progress display is turned off.
+    fn progress_text(&self, items: &Vec<Item>) -> Option<String> {
+        const BAR_WIDTH: usize = 5;
+        let (complete, total) = tree::progress(items, self.config.progress_ignore_disabled);
+        if total == 0 {
+            return None;
+        }
+        match self.config.progress {
+            ProgressStyle::Count => Some(format!("({}/{})", complete, total)),
+            ProgressStyle::Bar => {
+                let filled = (complete * BAR_WIDTH + total / 2) / total;
+                Some(format!(
+                    "{}{} {}/{}",
+                    "█".repeat(filled), "░".repeat(BAR_WIDTH - filled), complete, total,
+                ))
+            },
+            ProgressStyle::Off => None,
+        }
+    }
+    /// Progress over the whole list, for the list's title
+    pub fn list_progress(&self) -> String {
+        match self.progress_text(&self.container.list.items) {
+            Some(progress) => format!(" {}", progress),
+            None => String::new(),
+        }
+    }
+    pub fn status_line(&self) -> text::Spans<'static> {
+        let styles = &self.config.styles;
+        let mode = match self.mode {
+            NavMode::Navigate if self.visual_anchor.is_some() => "VISUAL",
+            NavMode::Navigate => "NAVIGATE",
+            NavMode::Input => "INPUT",
+            NavMode::Help => "HELP",
+            NavMode::Confirm => "CONFIRM",
+        };
+        let coords = if self.map.valid_positions.is_empty() {
+            "-".to_string()
+        } else {
+            self.map.position.iter()
+                .map(|p| p + 1)
+                .collect::<Vec<usize>>()
+                .to_coords()
+        };
+        let counts = tree::counts(&self.container.list.items);
+        let saved = if self.dirty { "modified" } else { "saved" };
+        let mut spans = vec![
+            text::Span::styled(
+                format!(" {} ", mode),
+                styles.selected.add_modifier(style::Modifier::REVERSED),
+            ),
+            text::Span::styled(
+                format!(
+                    " {} | {}/{} done, {} open | {} hidden | {} ",
+                    coords, counts.complete, counts.todos, counts.incomplete,
+                    counts.hidden, saved,
+                ),
+                styles.text,
+            ),
+        ];
+        let selected = self.selected_positions().len();
+        if selected > 0 {
+            spans.push(text::Span::styled(format!("| {} selected ", selected), styles.marked));
+        }
+        if let Some((msg, _)) = &self.status_msg {
+            spans.push(text::Span::styled(format!("| {}", msg), styles.warning));
+        }
+        text::Spans::from(spans)
     }
     pub fn push_log(&mut self, msg: impl AsRef<str>) {
         self.d_buffer.push(LogMsg::log(msg));
@@ -174,17 +843,18 @@ impl Navigator {
         for msg in rev_buf.iter() {
             let s_color;
             let s_prefix;
+            let styles = &self.config.styles;
             match msg.log_type {
                 LogType::Log => {
-                    s_color = style::Style::default().fg(style::Color::Cyan);
+                    s_color = styles.log;
                     s_prefix = "LOG : ";
                 },
                 LogType::Warning => {
-                    s_color = style::Style::default().fg(style::Color::Yellow);
+                    s_color = styles.warning;
                     s_prefix = "WARN: ";
                 },
                 LogType::Error => {
-                    s_color = style::Style::default().fg(style::Color::Red);
+                    s_color = styles.error;
                     s_prefix = "DANG: ";
                 },
             }
@@ -201,6 +871,67 @@ impl Navigator {
         }
         items
     }
+    /// Builds the help overlay from the active keymap so that it always
+    /// reflects remapped keys.
+    pub fn help_lines(&self) -> Vec<text::Spans<'static>> {
+        let styles = &self.config.styles;
+        let keymap = &self.config.keymap;
+        let key_width = NavAction::BINDABLE.iter()
+            .map(|(action, _, _, _)| wrap::width(&keymap.keys_for(*action).join(" ")))
+            .max()
+            .unwrap_or(0)
+            .max(10);
+        let mut lines = Vec::new();
+        for category in ActionCategory::ALL.iter() {
+            if !lines.is_empty() {
+                lines.push(text::Spans::from(""));
+            }
+            lines.push(text::Spans::from(text::Span::styled(
+                category.title(),
+                styles.selected.add_modifier(style::Modifier::BOLD),
+            )));
+            for (action, _, action_category, description) in NavAction::BINDABLE.iter() {
+                if !action_category.eq(category) {
+                    continue;
+                }
+                let keys = keymap.keys_for(*action);
+                let keys = if keys.is_empty() {
+                    "(unbound)".to_string()
+                } else {
+                    keys.join(" ")
+                };
+                lines.push(text::Spans::from(vec![
+                    text::Span::styled(
+                        format!("  {:width$}  ", keys, width = key_width),
+                        styles.note,
+                    ),
+                    text::Span::styled(description.to_string(), styles.text),
+                ]));
+            }
+        }
+        lines.push(text::Spans::from(""));
+        lines.push(text::Spans::from(text::Span::styled(
+            "Text input",
+            styles.selected.add_modifier(style::Modifier::BOLD),
+        )));
+        for (keys, description) in [
+            ("<Enter>", "Save the item"),
+            ("<Esc>", "Cancel"),
+            ("<BS>", "Delete the last character"),
+            ("<C-t>", "Switch between todo and note"),
+            ("<Tab>", "Indent the new item (rapid entry)"),
+            ("<S-Tab>", "Outdent the new item (rapid entry)"),
+        ] {
+            lines.push(text::Spans::from(vec![
+                text::Span::styled(
+                    format!("  {:width$}  ", keys, width = key_width),
+                    styles.note,
+                ),
+                text::Span::styled(description, styles.text),
+            ]));
+        }
+        lines
+    }
     pub fn get_todo_item_location(&mut self) -> Vec<usize> {
         let mut action_vec = self.map.position.clone();
         action_vec.reverse();
@@ -209,9 +940,17 @@ impl Navigator {
         });
         action_vec
     }
-    pub fn handle_win_buf(&mut self, initial: bool) {
+    /// Rows taken by each visible item at the current width, in display
+    /// order. Worked out once per draw and shared by `handle_win_buf` and
+    /// `get_list`.
+    pub fn row_heights(&self) -> Vec<u16> {
+        let mut counter = RowCounter { nav: self, heights: Vec::new(), };
+        tree::walk(&self.container.list.items, self.display_hidden, &mut counter);
+        counter.heights
+    }
+    pub fn handle_win_buf(&mut self, initial: bool, heights: &Vec<u16>) {
         let changed = self.map.window_buffer.set_size(
-            self.height, &self.map.position, &self.map.valid_positions, initial
+            self.height, &self.map.position, &self.map.valid_positions, heights, initial
         );
         if changed {
             self.push_log(format!(
@@ -223,14 +962,203 @@ impl Navigator {
             ));
         }
     }
+    fn removal_prompt(text: &str, children: usize) -> String {
+        const MAX_TEXT: usize = 30;
+        let short = wrap::truncate(text, MAX_TEXT);
+        match children {
+            0 => format!("Delete '{}'?", short),
+            1 => format!("Delete '{}' and 1 child?", short),
+            n => format!("Delete '{}' and {} children?", short, n),
+        }
+    }
+    fn remove_item(&mut self, ctx: &mut Ctx) {
+        // the cursor still has a position on an empty list
+        if self.map.valid_positions.is_empty() {
+            self.push_status("Nothing to remove");
+            return;
+        }
+        self.push_log("Removing selected items");
+        let positions = tree::top_most(&self.selection());
+        tree::remove_items(&mut self.container.list.items, &positions);
+        if !self.save_and_reload(ctx) {
+            return;
+        }
+        if positions.len() > 1 {
+            self.push_status(format!("{} items removed", positions.len()));
+        } else {
+            self.push_status("Item removed");
+        }
+    }
+    fn archive(&mut self, ctx: &mut Ctx, positions: Vec<Vec<usize>>) {
+        self.push_log(format!("Archiving {} items", positions.len()));
+        let snapshot = match archive::Snapshot::take(ctx) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                self.push_status("Failed to archive");
+                self.push_error(e);
+                return;
+            },
+        };
+        match archive::archive_items(ctx, &mut self.container, &positions) {
+            Ok(count) => match self.save_and_record(ctx, Some(snapshot)) {
+                Ok(_) => {
+                    self.push_status(format!("Archived {} items", count));
+                },
+                Err(snapshot) => {
+                    // the items would be in both files, take them out of the
+                    // archive and back into the list
+                    if let Some(Err(e)) = snapshot.map(archive::Snapshot::restore) {
+                        self.push_error(e);
+                    }
+                    self.reload(ctx);
+                    self.push_status("Failed to archive");
+                },
+            },
+            Err(e) => {
+                // drop the partially applied move
+                self.reload(ctx);
+                self.push_status("Failed to archive");
+                self.push_error(e);
+            },
+        }
+    }
+    /// Asks for confirmation before taking `action`.
+    pub fn ask(&mut self, prompt: impl AsRef<str>, action: NavAction) {
+        self.ask_choices(prompt, vec![ ('y', "yes", action), ]);
+    }
+    pub fn ask_choices(
+        &mut self, prompt: impl AsRef<str>, choices: Vec<(char, &'static str, NavAction)>,
+    ) {
+        self.confirm = Some(Confirm { prompt: prompt.as_ref().to_string(), choices, });
+        self.mode = NavMode::Confirm;
+    }
+    /// Previews how many completed items below `scope` would be cleared and
+    /// asks whether to remove or hide them.
+    fn offer_clear_completed(&mut self, scope: Vec<usize>) {
+        let positions = tree::completed_under(&self.container.list.items, &scope);
+        if positions.is_empty() {
+            self.push_status("Nothing completed to clear");
+            return;
+        }
+        let children = positions.iter()
+            .filter_map(|pos| tree::item_at(&self.container.list.items, pos))
+            .map(|item| tree::descendant_count(&item.sub_items))
+            .sum::<usize>();
+        let prompt = if children > 0 {
+            format!("Clear {} completed items ({} with children)?", positions.len(), positions.len() + children)
+        } else {
+            format!("Clear {} completed items?", positions.len())
+        };
+        self.clear_scope = scope;
+        self.ask_choices(prompt, vec![
+            ('r', "remove", NavAction::ClearCompletedRemove),
+            ('h', "hide", NavAction::ClearCompletedHide),
+        ]);
+    }
+    fn clear_completed(&mut self, ctx: &mut Ctx, hide: bool) {
+        let positions = tree::completed_under(&self.container.list.items, &self.clear_scope);
+        if hide {
+            for pos in positions.iter() {
+                if let Some(item) = tree::item_at_mut(&mut self.container.list.items, pos) {
+                    item.hidden = true;
+                }
+            }
+        } else {
+            tree::remove_items(&mut self.container.list.items, &positions);
+        }
+        if !self.save_and_reload(ctx) {
+            return;
+        }
+        let verb = if hide { "Hid" } else { "Removed" };
+        self.push_status(format!("{} {} completed items", verb, positions.len()));
+    }
+    /// Walks up from `pos`, completing each todo ancestor whose todo children
+    /// are all complete and reopening completed ones that have an incomplete
+    /// todo child. Disabled children are left out of the decision.
+    fn propagate_status(&mut self, pos: &Vec<usize>) {
+        let mut parent = pos.clone();
+        parent.pop();
+        while !parent.is_empty() {
+            if let Some(item) = tree::item_at_mut(&mut self.container.list.items, &parent) {
+                let mut todos = 0;
+                let mut complete = 0;
+                for child in item.sub_items.iter() {
+                    if let ItemType::Todo = child.item_type {
+                        match child.status {
+                            ItemStatus::Complete => {
+                                todos = todos + 1;
+                                complete = complete + 1;
+                            },
+                            ItemStatus::Incomplete => todos = todos + 1,
+                            ItemStatus::Disabled => {},
+                        }
+                    }
+                }
+                let is_todo = match item.item_type {
+                    ItemType::Todo => true,
+                    ItemType::Note => false,
+                };
+                if is_todo && todos > 0 {
+                    if todos == complete {
+                        item.status = ItemStatus::Complete;
+                    } else if let ItemStatus::Complete = item.status {
+                        item.status = ItemStatus::Incomplete;
+                    }
+                }
+            }
+            parent.pop();
+        }
+    }
+    fn complete_all(items: &mut Vec<Item>) {
+        for item in items.iter_mut() {
+            let is_open_todo = matches!(
+                (&item.item_type, &item.status), (ItemType::Todo, ItemStatus::Incomplete)
+            );
+            if is_open_todo {
+                item.status = ItemStatus::Complete;
+            }
+            Self::complete_all(&mut item.sub_items);
+        }
+    }
+    /// Offers to complete the incomplete todo descendants of a todo that has
+    /// just been completed.
+    fn offer_complete_descendants(&mut self, pos: &Vec<usize>) {
+        let item = match tree::item_at(&self.container.list.items, pos) {
+            Some(item) => item,
+            None => return,
+        };
+        let is_complete_todo = matches!(
+            (&item.item_type, &item.status), (ItemType::Todo, ItemStatus::Complete)
+        );
+        let (complete, total) = tree::progress(&item.sub_items, true);
+        if is_complete_todo && total > complete {
+            self.ask(
+                format!("Complete {} open descendants?", total - complete),
+                NavAction::CompleteDescendants,
+            );
+        }
+    }
     pub fn take_movement_action(&mut self, ctx: &mut Ctx) -> bool {
         let new_hash = Self::get_file_hash(ctx);
-        if !self.file_hash.eq(&new_hash) {
+        // an unsaved row being typed would be lost, it is saved over any
+        // outside change instead
+        if !self.file_hash.eq(&new_hash) && self.insert_origin.is_none() {
             self.reload(ctx);
+            self.push_status("List changed on disk, reloaded");
+        }
+        if self.read_only && self.action.modifies() {
+            self.push_status("The archive is read-only");
+            self.action = NavAction::NoAction;
         }
         let keep_run;
         let is_movement;
         match self.action {
+            NavAction::ToggleDetail => {
+                self.push_log("Toggling detail pane");
+                self.detail = !self.detail;
+                keep_run = true;
+                is_movement = false;
+            },
             NavAction::ToggleDebug => {
                 self.push_log("Toggling debug mode");
                 self.debug = !self.debug;
@@ -268,32 +1196,97 @@ impl Navigator {
             NavAction::ToggleShowHidden => {
                 self.push_log("Hidden toggled");
                 self.display_hidden = !self.display_hidden;
-                // TODO: Do not save and reload here, just reinit map
-                self.save_and_reload(ctx);
+                self.rebuild_map();
                 keep_run = true;
                 is_movement = true;
             },
             NavAction::CycleItemStatus => {
                 self.push_log("Cycling status");
-                let mut action_vec = self.get_todo_item_location();
-                self.container.act_on_item_at(&mut action_vec, ItemAction::CycleStatus);
-                self.save_and_reload(ctx);
+                let positions = self.selection();
+                self.act_on_selection(|| ItemAction::CycleStatus);
+                if self.config.auto_complete_parents {
+                    for pos in positions.iter() {
+                        self.propagate_status(pos);
+                    }
+                }
+                if self.save_and_reload(ctx) {
+                    if positions.len() > 1 {
+                        self.push_status(format!("Status changed on {} items", positions.len()));
+                    } else {
+                        self.push_status("Status changed");
+                        if self.config.complete_descendants {
+                            let pos = self.map.position.clone();
+                            self.offer_complete_descendants(&pos);
+                        }
+                    }
+                }
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::MarkComplete | NavAction::MarkIncomplete | NavAction::MarkDisabled => {
+                self.push_log("Setting status");
+                let action = self.action;
+                let selection = self.selection();
+                let single = if selection.len() == 1 { selection.first().cloned() } else { None };
+                let count = self.set_status(action);
+                if count == 0 {
+                    self.push_status("Notes have no status");
+                } else if self.save_and_reload(ctx) {
+                    let status = match action {
+                        NavAction::MarkComplete => "complete",
+                        NavAction::MarkDisabled => "disabled",
+                        _ => "incomplete",
+                    };
+                    if let Some(pos) = single {
+                        self.push_status(format!("Marked {}", status));
+                        if self.config.complete_descendants {
+                            self.offer_complete_descendants(&pos);
+                        }
+                    } else {
+                        self.push_status(format!("Marked {} items {}", count, status));
+                    }
+                }
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::CompleteDescendants => {
+                self.push_log("Completing descendants");
+                let pos = self.map.position.clone();
+                if let Some(item) = tree::item_at_mut(&mut self.container.list.items, &pos) {
+                    Self::complete_all(&mut item.sub_items);
+                }
+                if self.config.auto_complete_parents {
+                    self.propagate_status(&pos);
+                }
+                if self.save_and_reload(ctx) {
+                    self.push_status("Descendants completed");
+                }
                 keep_run = true;
                 is_movement = false;
             },
             NavAction::ToggleItemHidden => {
                 self.push_log("Toggling item hidden");
-                let mut action_vec = self.get_todo_item_location();
-                self.container.act_on_item_at(&mut action_vec, ItemAction::ToggleHidden);
-                self.save_and_reload(ctx);
+                let count = self.act_on_selection(|| ItemAction::ToggleHidden);
+                if self.save_and_reload(ctx) {
+                    if count > 1 {
+                        self.push_status(format!("Visibility toggled on {} items", count));
+                    } else {
+                        self.push_status("Item visibility toggled");
+                    }
+                }
                 keep_run = true;
                 is_movement = false;
             },
             NavAction::ToggleItemType => {
                 self.push_log("Toggling item type at position");
-                let mut action_vec = self.get_todo_item_location();
-                self.container.act_on_item_at(&mut action_vec, ItemAction::ToggleType);
-                self.save_and_reload(ctx);
+                let count = self.act_on_selection(|| ItemAction::ToggleType);
+                if self.save_and_reload(ctx) {
+                    if count > 1 {
+                        self.push_status(format!("Type toggled on {} items", count));
+                    } else {
+                        self.push_status("Item type toggled");
+                    }
+                }
                 keep_run = true;
                 is_movement = false;
             },
@@ -308,13 +1301,83 @@ impl Navigator {
                 let mut action_vec = self.get_todo_item_location();
                 self.container.act_on_item_at(
                     &mut action_vec,
-                    ItemAction::Add(ItemType::Todo, self.i_buffer.clone()),
+                    ItemAction::Add(self.input_type(), self.i_buffer.clone()),
                 );
                 self.i_buffer = String::new();
-                self.save_and_reload(ctx);
+                self.input_note = false;
+                if self.save_and_reload(ctx) {
+                    self.push_status("Item added");
+                }
                 keep_run = true;
                 is_movement = false;
             },
+            NavAction::PreInsertBelow | NavAction::PreInsertAbove => {
+                self.push_log("Preparing to insert sibling");
+                let below = self.action.eq(&NavAction::PreInsertBelow);
+                self.begin_insert(below);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::PreRapidEntry => {
+                self.push_log("Starting rapid entry");
+                self.begin_insert(true);
+                if self.mode.eq(&NavMode::Input) {
+                    self.rapid_entry = Some((0, self.map.position.clone()));
+                }
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::FinishRapidEntry => {
+                self.push_log("Finishing rapid entry");
+                self.finish_rapid_entry(ctx);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::EditItem | NavAction::EditSubtree => {
+                if self.map.valid_positions.is_empty() {
+                    self.push_status("Nothing to edit");
+                } else {
+                    self.push_log("Opening editor");
+                    self.edit = Some(EditRequest {
+                        pos: self.map.position.clone(),
+                        subtree: self.action.eq(&NavAction::EditSubtree),
+                    });
+                }
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::PasteItems => {
+                self.push_log("Pasting items");
+                self.paste_items(ctx);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::InsertItem => {
+                self.push_log("Inserting item");
+                let pos = self.map.position.clone();
+                let item_type = self.input_type();
+                if let Some(item) = tree::item_at_mut(&mut self.container.list.items, &pos) {
+                    item.text = self.i_buffer.clone();
+                    item.item_type = item_type;
+                }
+                self.i_buffer = String::new();
+                self.input_note = false;
+                self.insert_origin = None;
+                if self.save_and_reload(ctx) {
+                    self.push_status("Item added");
+                }
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::CancelInsert => {
+                self.push_log("Dropping inserted item");
+                if let Some(origin) = self.insert_origin.take() {
+                    self.map.position = origin;
+                }
+                self.reload(ctx);
+                keep_run = true;
+                is_movement = true;
+            },
             NavAction::PreAddRootItem => {
                 self.push_log("Preparing to add root item");
                 self.mode = NavMode::Input;
@@ -325,21 +1388,168 @@ impl Navigator {
                 self.push_log("Adding root item");
                 self.container.act_on_item_at(
                     &mut Vec::new(),
-                    ItemAction::Add(ItemType::Todo, self.i_buffer.clone()),
+                    ItemAction::Add(self.input_type(), self.i_buffer.clone()),
                 );
                 self.i_buffer = String::new();
-                self.save_and_reload(ctx);
+                self.input_note = false;
+                if self.save_and_reload(ctx) {
+                    self.push_status("Item added");
+                }
                 keep_run = true;
                 is_movement = false;
             },
             NavAction::RemoveItem => {
-                self.push_log("Removing item at position");
-                let mut action_vec = self.get_todo_item_location();
-                self.container.act_on_item_at(
-                    &mut action_vec,
-                    ItemAction::Remove,
+                let positions = tree::top_most(&self.selection());
+                let prompt = if positions.len() > 1 {
+                    let children = positions.iter()
+                        .filter_map(|pos| tree::item_at(&self.container.list.items, pos))
+                        .map(|item| tree::descendant_count(&item.sub_items))
+                        .sum::<usize>();
+                    Some(match children {
+                        0 => format!("Delete {} selected items?", positions.len()),
+                        n => format!("Delete {} selected items and {} children?", positions.len(), n),
+                    })
+                } else {
+                    match tree::item_at(&self.container.list.items, &positions[0]) {
+                        Some(item) => {
+                            let children = tree::descendant_count(&item.sub_items);
+                            if children > 0 || self.config.confirm_leaf_removal {
+                                Some(Self::removal_prompt(&item.text, children))
+                            } else {
+                                None
+                            }
+                        },
+                        None => None,
+                    }
+                };
+                match prompt {
+                    Some(prompt) => {
+                        self.ask(prompt, NavAction::RemoveItemConfirmed);
+                        keep_run = true;
+                        is_movement = false;
+                    },
+                    None => {
+                        self.remove_item(ctx);
+                        keep_run = true;
+                        is_movement = true;
+                    },
+                }
+            },
+            NavAction::RemoveItemConfirmed => {
+                self.remove_item(ctx);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::ArchiveItem => {
+                let pos = self.map.position.clone();
+                if tree::item_at(&self.container.list.items, &pos).map(tree::is_complete).unwrap_or(false) {
+                    self.archive(ctx, vec![pos]);
+                } else {
+                    self.push_status("Only completed items can be archived");
+                }
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::ArchiveCompleted => {
+                let count = tree::completed_under(
+                    &self.container.list.items, &self.map.position,
+                ).len();
+                if count == 0 {
+                    self.push_status("Nothing completed to archive");
+                } else {
+                    self.ask(
+                        format!("Archive {} completed items?", count),
+                        NavAction::ArchiveCompletedConfirmed,
+                    );
+                }
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::ArchiveCompletedConfirmed => {
+                let positions = tree::completed_under(
+                    &self.container.list.items, &self.map.position,
                 );
-                self.save_and_reload(ctx);
+                self.archive(ctx, positions);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::ClearCompleted => {
+                self.offer_clear_completed(Vec::new());
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::ClearCompletedBelow => {
+                let scope = self.map.position.clone();
+                self.offer_clear_completed(scope);
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::ClearCompletedRemove => {
+                self.push_log("Removing completed items");
+                self.clear_completed(ctx, false);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::ClearCompletedHide => {
+                self.push_log("Hiding completed items");
+                self.clear_completed(ctx, true);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::Undo => {
+                self.push_log("Undoing last change");
+                self.undo(ctx);
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::ToggleVisual => {
+                if self.visual_anchor.is_some() {
+                    self.push_log("Leaving visual selection");
+                    self.visual_anchor = None;
+                } else {
+                    self.push_log("Starting visual selection");
+                    self.visual_anchor = Some(self.map.position.clone());
+                }
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::ToggleMark => {
+                let pos = self.map.position.clone();
+                match self.marked.iter().position(|marked| marked.eq(&pos)) {
+                    Some(i) => {
+                        self.marked.remove(i);
+                    },
+                    None => self.marked.push(pos),
+                }
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::ClearSelection => {
+                self.clear_selection();
+                keep_run = true;
+                is_movement = false;
+            },
+            NavAction::IndentItems => {
+                self.push_log("Indenting items");
+                self.restructure(ctx, tree::indent_items, "Indented");
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::OutdentItems => {
+                self.push_log("Outdenting items");
+                self.restructure(ctx, tree::outdent_items, "Outdented");
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::MoveItemsUp => {
+                self.push_log("Moving items up");
+                self.restructure(ctx, |items, positions| tree::move_items(items, positions, false), "Moved up");
+                keep_run = true;
+                is_movement = true;
+            },
+            NavAction::MoveItemsDown => {
+                self.push_log("Moving items down");
+                self.restructure(ctx, |items, positions| tree::move_items(items, positions, true), "Moved down");
                 keep_run = true;
                 is_movement = true;
             },
@@ -358,6 +1568,13 @@ impl Navigator {
                 keep_run = true;
                 is_movement = true;
             },
+            NavAction::ToggleHelp => {
+                self.push_log("Opening help");
+                self.mode = NavMode::Help;
+                self.help_scroll = 0;
+                keep_run = true;
+                is_movement = false;
+            },
             //NavAction::GoToInnerLevel => {
             //    self.push_log("Innermost");
             //    keep_run = true;
@@ -366,24 +1583,126 @@ impl Navigator {
         };
         if is_movement {
             self.move_action();
-            self.handle_win_buf(false);
         }
         keep_run
     }
     pub fn take_action(&mut self, ctx: &mut Ctx) -> bool {
         match self.mode {
             NavMode::Navigate => self.take_movement_action(ctx),
-            NavMode::Input => true,
+            NavMode::Input | NavMode::Help | NavMode::Confirm => true,
+        }
+    }
+    /// Resolves key presses through the keymap, holding on to the keys of an
+    /// unfinished sequence until it either completes or stops matching.
+    fn action_from_event(&mut self, event: TermEvent) -> NavAction {
+        let key = match event {
+            TermEvent::Key(key) => KeyChord::from_event(&key),
+            TermEvent::Paste(_) | TermEvent::Tick => return NavAction::NoAction,
+        };
+        self.pending_keys.push(key);
+        match self.config.keymap.lookup(&self.pending_keys) {
+            Lookup::Action(action) => {
+                self.pending_keys.clear();
+                action
+            },
+            Lookup::Pending => NavAction::NoAction,
+            Lookup::NoMatch => {
+                let retry = self.pending_keys.len() > 1;
+                self.pending_keys.clear();
+                if !retry {
+                    return NavAction::NoAction;
+                }
+                // the last key may start a sequence of its own
+                match self.config.keymap.lookup(&[key]) {
+                    Lookup::Action(action) => action,
+                    Lookup::Pending => {
+                        self.pending_keys.push(key);
+                        NavAction::NoAction
+                    },
+                    Lookup::NoMatch => NavAction::NoAction,
+                }
+            },
         }
     }
     pub fn handle_input(&mut self, event: TermEvent) {
+        if let TermEvent::Tick = event {
+            self.tick_status();
+        }
+        if let TermEvent::Paste(text) = event {
+            self.handle_paste(text);
+            return;
+        }
         match self.mode {
             NavMode::Navigate => {
-                self.action = NavAction::from_event(event);
+                self.action = self.action_from_event(event);
+            },
+            NavMode::Confirm => {
+                let key = match event {
+                    TermEvent::Key(key) => key,
+                    TermEvent::Paste(_) | TermEvent::Tick => return,
+                };
+                self.mode = NavMode::Navigate;
+                let chosen = match (key.code, self.confirm.take()) {
+                    (KeyCode::Char(c), Some(confirm)) => confirm.choices.iter()
+                        .find(|(choice, _, _)| choice.eq_ignore_ascii_case(&c))
+                        .map(|(_, _, action)| *action),
+                    _ => None,
+                };
+                match chosen {
+                    Some(action) => {
+                        self.action = action;
+                    },
+                    None => {
+                        self.action = NavAction::NoAction;
+                        self.push_status("Cancelled");
+                    },
+                }
+            },
+            NavMode::Help => {
+                let key = match event {
+                    TermEvent::Key(key) => key,
+                    TermEvent::Paste(_) | TermEvent::Tick => return,
+                };
+                let bound = self.config.keymap.lookup(&[KeyChord::from_event(&key)]);
+                match bound {
+                    Lookup::Action(NavAction::Next) => {
+                        self.help_scroll = self.help_scroll + 1;
+                    },
+                    Lookup::Action(NavAction::Prev) => {
+                        self.help_scroll = self.help_scroll.saturating_sub(1);
+                    },
+                    Lookup::Action(NavAction::ToggleHelp) => {
+                        self.mode = NavMode::Navigate;
+                        self.action = NavAction::NoAction;
+                    },
+                    _ => if let KeyCode::Esc = key.code {
+                        self.mode = NavMode::Navigate;
+                        self.action = NavAction::NoAction;
+                    },
+                }
             },
             NavMode::Input => {
                 match event {
                     TermEvent::Key(key) => match key.code {
+                        KeyCode::Enter if self.rapid_entry.is_some() => {
+                            if self.i_buffer.is_empty() {
+                                self.mode = NavMode::Navigate;
+                                self.action = NavAction::FinishRapidEntry;
+                            } else {
+                                self.commit_rapid_entry();
+                            }
+                        },
+                        KeyCode::Esc if self.rapid_entry.is_some() => {
+                            self.mode = NavMode::Navigate;
+                            self.action = NavAction::FinishRapidEntry;
+                            self.i_buffer = String::new();
+                        },
+                        KeyCode::Tab if self.rapid_entry.is_some() => {
+                            self.shift_rapid_entry(true);
+                        },
+                        KeyCode::BackTab if self.rapid_entry.is_some() => {
+                            self.shift_rapid_entry(false);
+                        },
                         KeyCode::Enter => {
                             // TODO: Finish implementing typing
                             self.mode = NavMode::Navigate;
@@ -394,18 +1713,42 @@ impl Navigator {
                                 NavAction::PreAddItem => {
                                     self.action = NavAction::AddItem;
                                 },
+                                NavAction::PreInsertBelow | NavAction::PreInsertAbove => {
+                                    self.action = if self.i_buffer.is_empty() {
+                                        NavAction::CancelInsert
+                                    } else {
+                                        NavAction::InsertItem
+                                    };
+                                },
                                 _ => {},
                             }
                         },
                         KeyCode::Esc => {
                             self.mode = NavMode::Navigate;
-                            self.action = NavAction::NoAction;
+                            self.action = match self.action {
+                                NavAction::PreInsertBelow | NavAction::PreInsertAbove => {
+                                    NavAction::CancelInsert
+                                },
+                                _ => NavAction::NoAction,
+                            };
+                            self.i_buffer = String::new();
+                            self.input_note = false;
+                        },
+                        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            self.input_note = !self.input_note;
+                            if self.insert_origin.is_some() {
+                                let item_type = self.input_type();
+                                let pos = self.map.position.clone();
+                                if let Some(item) = tree::item_at_mut(&mut self.container.list.items, &pos) {
+                                    item.item_type = item_type;
+                                }
+                            }
                         },
                         KeyCode::Char(c) => {
                             self.i_buffer.push(c);
                         },
                         KeyCode::Backspace => {
-                            self.i_buffer.pop();
+                            wrap::pop_grapheme(&mut self.i_buffer);
                         },
                         _ => {},
                     },
@@ -414,118 +1757,238 @@ impl Navigator {
             },
         }
     }
-    pub fn item_as_widget(&self, item: &Item, items: &mut Vec<widgets::ListItem>, pos: &mut Vec<usize>) {
-        let mut indent_str = String::new();
-        for _ in 0..pos.len() - 1 {
-            indent_str.push_str("    ");
-        }
+    /// Renders an item as one or more rows, wrapping its text below itself
+    /// so that continuation rows line up after the status column.
+    pub fn item_lines(&self, item: &Item, pos: &Vec<usize>, marked: bool) -> Vec<text::Spans<'static>> {
+        let styles = &self.config.styles;
+        let indent_str = " ".repeat((pos.len() - 1) * self.config.indent as usize);
+        let prefix_width = wrap::width(&indent_str) + 4;
         let indent = text::Span::from(indent_str);
         let status = match item.item_type {
             ItemType::Todo => {
                 match item.status {
                     ItemStatus::Complete => {
-                        let color = if item.hidden {
-                            style::Color::DarkGray
+                        let s_style = if item.hidden {
+                            styles.hidden
                         } else {
-                            style::Color::Green
+                            styles.complete
                         };
-                        text::Span::styled(
-                            "[x] ",
-                            style::Style::default().fg(color)
-                        )
+                        text::Span::styled("[x] ", s_style)
                     },
                     ItemStatus::Incomplete => {
-                        let color = if item.hidden {
-                            style::Color::DarkGray
+                        let s_style = if item.hidden {
+                            styles.hidden
                         } else {
-                            style::Color::Red
+                            styles.incomplete
                         };
-                        text::Span::styled(
-                            "[ ] ",
-                            style::Style::default().fg(color)
-                        )
+                        text::Span::styled("[ ] ", s_style)
                     },
                     ItemStatus::Disabled => {
-                        let color = if item.hidden {
-                            style::Color::DarkGray
+                        let s_style = if item.hidden {
+                            styles.hidden
                         } else {
-                            style::Color::Yellow
+                            styles.disabled
                         };
-                        text::Span::styled(
-                            "[-] ",
-                            style::Style::default().fg(color)
-                        )
+                        text::Span::styled("[-] ", s_style)
                     },
                 }
             },
             ItemType::Note => {
-                let color = if item.hidden {
-                    style::Color::DarkGray
+                let s_style = if item.hidden {
+                    styles.hidden
                 } else {
-                    style::Color::Cyan
+                    styles.note
                 };
-                text::Span::styled(
-                    "-   ",
-                    style::Style::default().fg(color)
-                )
+                text::Span::styled("-   ", s_style)
             },
         };
         // is item selected?
-        let text = if (*pos).eq(&self.map.position) {
-            text::Span::styled(
-                item.text.clone(),
-                style::Style::default().fg(style::Color::Cyan)
-            )
+        let (text, t_style) = if self.insert_origin.is_some() && (*pos).eq(&self.map.position) {
+            (format!("{}|", self.i_buffer), styles.selected)
+        } else if (*pos).eq(&self.map.position) {
+            (item.text.clone(), styles.selected)
+        } else if marked {
+            (item.text.clone(), styles.marked)
         } else {
             if item.hidden {
-                text::Span::styled(
-                    item.text.clone(),
-                    style::Style::default().fg(style::Color::DarkGray)
-                )
+                (item.text.clone(), styles.hidden)
             } else {
-                text::Span::styled(
-                    item.text.clone(),
-                    style::Style::default().fg(style::Color::White)
-                )
+                (item.text.clone(), styles.text)
             }
         };
-        items.push(widgets::ListItem::new(
-            text::Spans::from(vec![ indent, status, text, ])
-        ));
+        // nothing is known of the width before the first draw
+        let text_width = if self.width == 0 {
+            usize::MAX
+        } else {
+            (self.width.saturating_sub(WindowBufferBounds::X_PADDING) as usize)
+                .saturating_sub(prefix_width)
+        };
+        let wrapped = wrap::wrap(&text, text_width);
+        let last_width = wrapped.last().map(|line| wrap::width(line)).unwrap_or(0);
+        let mut lines = Vec::new();
+        let mut first = Some(vec![ indent, status, ]);
+        for chunk in wrapped.into_iter() {
+            let mut spans = first.take()
+                .unwrap_or_else(|| vec![ text::Span::from(" ".repeat(prefix_width)), ]);
+            spans.push(text::Span::styled(chunk, t_style));
+            lines.push(spans);
+        }
+        if let Some(progress) = self.progress_text(&item.sub_items) {
+            let p_style = if item.hidden { styles.hidden } else { styles.note };
+            let progress_width = wrap::width(&progress) + 1;
+            if last_width.saturating_add(progress_width) > text_width {
+                lines.push(vec![
+                    text::Span::from(" ".repeat(prefix_width)),
+                    text::Span::styled(progress, p_style),
+                ]);
+            } else if let Some(last) = lines.last_mut() {
+                last.push(text::Span::styled(format!(" {}", progress), p_style));
+            }
+        }
+        lines.into_iter().map(text::Spans::from).collect()
     }
-    pub fn item_to_list_items(
-        &self, item: &Item, items: &mut Vec<widgets::ListItem>,
-        iter_state: &mut Vec<usize>, lines: &mut u16
-    ) {
-        if !self.display_hidden && item.hidden {
-            return;
+    /// Describes the selected item for the detail pane in at most `height`
+    /// rows of `width` columns, finishing with a preview of its children as
+    /// room allows.
+    pub fn detail_lines(&self, width: usize, height: usize) -> Vec<text::Spans<'static>> {
+        let styles = &self.config.styles;
+        let item = match tree::item_at(&self.container.list.items, &self.map.position) {
+            Some(item) if !self.map.valid_positions.is_empty() => item,
+            _ => return vec![ text::Spans::from(text::Span::styled("No item selected", styles.hidden)), ],
+        };
+        let mut lines = Vec::new();
+        for line in wrap::wrap(&item.text, width) {
+            lines.push(text::Spans::from(text::Span::styled(line, styles.text)));
+        }
+        lines.push(text::Spans::from(""));
+        let (item_type, status) = match item.item_type {
+            ItemType::Todo => match item.status {
+                ItemStatus::Complete => ("todo", Some(("complete", styles.complete))),
+                ItemStatus::Incomplete => ("todo", Some(("incomplete", styles.incomplete))),
+                ItemStatus::Disabled => ("todo", Some(("disabled", styles.disabled))),
+            },
+            ItemType::Note => ("note", None),
+        };
+        let field = |label: &str, value: String, style: style::Style| {
+            text::Spans::from(vec![
+                text::Span::styled(format!("{:<12}", label), styles.note),
+                text::Span::styled(value, style),
+            ])
+        };
+        lines.push(field("Type", item_type.to_string(), styles.text));
+        if let Some((status, s_style)) = status {
+            lines.push(field("Status", status.to_string(), s_style));
+        }
+        lines.push(field("Hidden", if item.hidden { "yes" } else { "no" }.to_string(), styles.text));
+        let coords = self.map.position.iter()
+            .map(|p| p + 1)
+            .collect::<Vec<usize>>()
+            .to_coords();
+        lines.push(field("Position", coords, styles.text));
+        lines.push(field(
+            "Descendants", tree::descendant_count(&item.sub_items).to_string(), styles.text,
+        ));
+        let (complete, total) = tree::progress(&item.sub_items, self.config.progress_ignore_disabled);
+        if total > 0 {
+            lines.push(field(
+                "Done", format!("{}/{} ({}%)", complete, total, complete * 100 / total), styles.text,
+            ));
         }
-        *lines = (*lines) + 1;
-        // TODO: Handle scrolling via height and y_pos
-        if self.map.window_buffer.is_in_view(*lines) {
-            self.item_as_widget(&item, items, iter_state);
+        if item.sub_items.is_empty() || lines.len() + 2 > height {
+            return lines;
         }
-        let mut i = 0;
-        for sub_item in item.sub_items.iter() {
-            iter_state.push(i);
-            self.item_to_list_items(sub_item, items, iter_state, lines);
-            iter_state.pop().unwrap();
-            i = i + 1;
+        lines.push(text::Spans::from(""));
+        let mut preview = PreviewBuilder { nav: self, width, lines: Vec::new(), };
+        tree::walk(&item.sub_items, self.display_hidden, &mut preview);
+        let room = height - lines.len();
+        let more = preview.lines.len().saturating_sub(room);
+        let shown = if more > 0 { room - 1 } else { preview.lines.len() };
+        lines.extend(preview.lines.into_iter().take(shown));
+        if more > 0 {
+            lines.push(text::Spans::from(text::Span::styled(
+                format!("… {} more", more + 1), styles.hidden,
+            )));
         }
+        lines
     }
-    pub fn get_list(&self) -> Vec<widgets::ListItem> {
-        let mut iter_state = Vec::new();
-        let mut items = Vec::new();
+    pub fn get_list<'a>(&'a self, heights: &'a Vec<u16>) -> Vec<widgets::ListItem<'a>> {
         // TODO: Handle empty list
-        let mut i = 0;
-        let mut lines = 0;
-        for item in self.container.list.items.iter() {
-            iter_state.push(i);
-            self.item_to_list_items(item, &mut items, &mut iter_state, &mut lines);
-            iter_state.pop().unwrap();
-            i = i + 1;
+        let mut builder = ListBuilder {
+            nav: self, items: Vec::new(), heights, index: 0, row: 0,
+            marked: self.selected_positions(),
+        };
+        tree::walk(&self.container.list.items, self.display_hidden, &mut builder);
+        builder.items
+    }
+}
+struct ListBuilder<'a> {
+    nav: &'a Navigator,
+    items: Vec<widgets::ListItem<'a>>,
+    heights: &'a Vec<u16>,
+    /// Index of the next item
+    index: usize,
+    /// First row of the next item
+    row: u16,
+    marked: Vec<Vec<usize>>,
+}
+impl<'a> ItemVisitor for ListBuilder<'a> {
+    fn enter(&mut self, item: &Item, pos: &Vec<usize>) {
+        let height = self.heights.get(self.index).copied().unwrap_or(1);
+        let first = self.row;
+        self.index = self.index + 1;
+        self.row = self.row + height;
+        let window = &self.nav.map.window_buffer;
+        // only items with a row in view are rendered
+        if first > window.max || first + height <= window.min {
+            return;
         }
-        items
+        let lines = self.nav.item_lines(item, pos, self.marked.contains(pos));
+        // items cut by the edges of the window keep the rows in view
+        let visible = lines.into_iter()
+            .enumerate()
+            .filter(|(i, _)| window.is_in_view(first + *i as u16))
+            .map(|(_, line)| line)
+            .collect::<Vec<text::Spans<'static>>>();
+        if !visible.is_empty() {
+            self.items.push(widgets::ListItem::new(visible));
+        }
+    }
+}
+/// Renders a compact, one row per item outline for the detail pane.
+struct PreviewBuilder<'a> {
+    nav: &'a Navigator,
+    width: usize,
+    lines: Vec<text::Spans<'static>>,
+}
+impl<'a> ItemVisitor for PreviewBuilder<'a> {
+    fn enter(&mut self, item: &Item, pos: &Vec<usize>) {
+        let styles = &self.nav.config.styles;
+        let (marker, m_style) = match item.item_type {
+            ItemType::Todo => match item.status {
+                ItemStatus::Complete => ("[x] ", styles.complete),
+                ItemStatus::Incomplete => ("[ ] ", styles.incomplete),
+                ItemStatus::Disabled => ("[-] ", styles.disabled),
+            },
+            ItemType::Note => ("- ", styles.note),
+        };
+        let indent = "  ".repeat(pos.len() - 1);
+        let used = wrap::width(&indent) + wrap::width(marker);
+        let t_style = if item.hidden { styles.hidden } else { styles.text };
+        self.lines.push(text::Spans::from(vec![
+            text::Span::from(indent),
+            text::Span::styled(marker, m_style),
+            text::Span::styled(wrap::truncate(&item.text, self.width.saturating_sub(used)), t_style),
+        ]));
+    }
+}
+/// Collects the rows each visible item takes, in display order.
+struct RowCounter<'a> {
+    nav: &'a Navigator,
+    heights: Vec<u16>,
+}
+impl<'a> ItemVisitor for RowCounter<'a> {
+    fn enter(&mut self, item: &Item, pos: &Vec<usize>) {
+        self.heights.push(self.nav.item_lines(item, pos, false).len() as u16);
     }
 }
 impl NavigateMap for Navigator {
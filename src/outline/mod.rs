@@ -0,0 +1,226 @@
+use {
+    crate::tree,
+    todo_core::{ Container, Item, ItemStatus, ItemType, },
+};
+const TAB_WIDTH: usize = 4;
+/// Bullet `write` uses for hidden items
+const HIDDEN_BULLET: &str = "~ ";
+/// One non-empty line of an outline
+pub struct Line {
+    /// Columns of leading whitespace, tabs expanded
+    pub indent: usize,
+    pub item_type: ItemType,
+    pub status: ItemStatus,
+    pub hidden: bool,
+    pub text: String,
+}
+fn strip_bullet(rest: &str) -> &str {
+    for bullet in [ "- ", "* ", "+ ", "• ", ] {
+        if let Some(body) = rest.strip_prefix(bullet) {
+            return body;
+        }
+    }
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && (rest[digits..].starts_with(". ") || rest[digits..].starts_with(") ")) {
+        return &rest[digits + 2..];
+    }
+    rest
+}
+const MARKERS: [&str; 4] = [ "[ ]", "[x]", "[X]", "[-]", ];
+fn marker_status(marker: &str) -> ItemStatus {
+    match marker {
+        "[ ]" => ItemStatus::Incomplete,
+        "[-]" => ItemStatus::Disabled,
+        _ => ItemStatus::Complete,
+    }
+}
+impl Line {
+    fn parse(line: &str, own_format: bool) -> Option<Self> {
+        let mut indent = 0;
+        for c in line.chars() {
+            match c {
+                ' ' => indent = indent + 1,
+                '\t' => indent = indent + TAB_WIDTH,
+                _ => break,
+            }
+        }
+        if own_format {
+            if let Some((hidden, body)) = Self::own_bullet(line.trim_start()) {
+                let (item_type, status, text) = Self::own_body(body);
+                return Some(Self { indent, item_type, status, hidden, text, });
+            }
+        }
+        let body = strip_bullet(line.trim());
+        let marked = MARKERS.iter().find_map(|marker| {
+            body.strip_prefix(marker).map(|text| (ItemType::Todo, marker_status(marker), text))
+        });
+        let (item_type, status, text) = match marked {
+            Some(marked) => marked,
+            None if own_format => (ItemType::Note, ItemStatus::Incomplete, body),
+            None => (ItemType::Todo, ItemStatus::Incomplete, body),
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        Some(Self { indent, item_type, status, hidden: false, text: text.to_string(), })
+    }
+    /// Splits off the bullet `write` puts on every line, also when an editor
+    /// dropped the space after it on an empty note.
+    fn own_bullet(line: &str) -> Option<(bool, &str)> {
+        match line {
+            "-" => Some((false, "")),
+            "~" => Some((true, "")),
+            _ => line.strip_prefix("- ").map(|body| (false, body))
+                .or(line.strip_prefix(HIDDEN_BULLET).map(|body| (true, body))),
+        }
+    }
+    /// Reads what follows the bullet exactly as `write_line` wrote it.
+    fn own_body(body: &str) -> (ItemType, ItemStatus, String) {
+        let mut found = (ItemType::Note, ItemStatus::Incomplete, body);
+        for marker in MARKERS.iter() {
+            if let Some(rest) = body.strip_prefix(marker) {
+                if rest.is_empty() || rest.starts_with(' ') {
+                    let text = rest.strip_prefix(' ').unwrap_or(rest);
+                    found = (ItemType::Todo, marker_status(marker), text);
+                    break;
+                }
+            }
+        }
+        let (item_type, status, text) = found;
+        (item_type, status, text.strip_prefix('\\').unwrap_or(text).to_string())
+    }
+}
+/// Reads indented and optionally bulleted text, one item per line. `[ ]`,
+/// `[x]` and `[-]` set the status; lines without one are todos, or notes when
+/// `own_format` is set. Blank lines are skipped. With `own_format`, lines in
+/// the form `write` uses are read back exactly, hidden bullet, escapes and
+/// surrounding spaces included.
+pub fn parse(text: &str, own_format: bool) -> Vec<Line> {
+    text.lines().filter_map(|line| Line::parse(line, own_format)).collect()
+}
+/// One line of `write`'s output. Text that would be read as a status marker
+/// or that starts with a backslash gets a backslash in front.
+fn write_line(
+    depth: usize, hidden: bool, item_type: &ItemType, status: &ItemStatus, text: &str,
+) -> String {
+    let marker = match item_type {
+        ItemType::Todo => match status {
+            ItemStatus::Complete => "[x] ",
+            ItemStatus::Incomplete => "[ ] ",
+            ItemStatus::Disabled => "[-] ",
+        },
+        ItemType::Note => "",
+    };
+    let escape = text.starts_with('\\')
+        || MARKERS.iter().any(|marker| text.starts_with(marker));
+    format!(
+        "{}{}{}{}{}\n",
+        " ".repeat(depth * TAB_WIDTH),
+        if hidden { HIDDEN_BULLET } else { "- " },
+        marker,
+        if escape { "\\" } else { "" },
+        text.replace('\n', " "),
+    )
+}
+fn write_item(item: &Item, depth: usize, out: &mut String) {
+    out.push_str(&write_line(depth, item.hidden, &item.item_type, &item.status, &item.text));
+    for child in item.sub_items.iter() {
+        write_item(child, depth + 1, out);
+    }
+}
+/// Writes `item` and its descendants as an outline that `parse` reads back
+/// with `own_format` set.
+pub fn write(item: &Item) -> String {
+    let mut out = String::new();
+    write_item(item, 0, &mut out);
+    out
+}
+/// Adds `lines` to the container, the least indented ones as consecutive
+/// children of `parent` starting at `index` and the rest nested below them
+/// by indentation. Returns the positions of the added items in display order.
+pub fn insert(
+    container: &mut Container, parent: &Vec<usize>, index: usize, lines: Vec<Line>,
+) -> Result<Vec<Vec<usize>>, String> {
+    let mut stack: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut added = Vec::new();
+    let mut index = index;
+    for line in lines.into_iter() {
+        while let Some((indent, _)) = stack.last() {
+            if *indent >= line.indent {
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+        let new_pos = match stack.last() {
+            Some((_, pos)) => tree::push_item(container, pos, line.item_type, &line.text),
+            None => {
+                let pos = tree::insert_item(container, parent, index, line.item_type, &line.text);
+                if let Some(pos) = &pos {
+                    index = pos[pos.len() - 1] + 1;
+                }
+                pos
+            },
+        }.ok_or(format!("Failed to add \"{}\"", line.text))?;
+        let item = tree::item_at_mut(&mut container.list.items, &new_pos)
+            .ok_or(format!("Failed to find \"{}\"", line.text))?;
+        item.status = line.status;
+        item.hidden = line.hidden;
+        added.push(new_pos.clone());
+        stack.push((line.indent, new_pos));
+    }
+    Ok(added)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn round_trip(hidden: bool, item_type: ItemType, status: ItemStatus, text: &str) -> Line {
+        let written = write_line(1, hidden, &item_type, &status, text);
+        let mut lines = parse(&written, true);
+        assert_eq!(lines.len(), 1, "{:?}", written);
+        lines.remove(0)
+    }
+    #[test]
+    fn notes_keep_marker_like_text() {
+        for text in [ "[ ] not a todo", "[x] not done", "[-]", "\\escaped", "plain", ] {
+            let line = round_trip(false, ItemType::Note, ItemStatus::Incomplete, text);
+            assert!(matches!(line.item_type, ItemType::Note), "{}", text);
+            assert_eq!(line.text, text);
+        }
+    }
+    #[test]
+    fn todos_keep_status_and_text() {
+        let line = round_trip(false, ItemType::Todo, ItemStatus::Complete, "[ ] inside");
+        assert!(matches!(line.item_type, ItemType::Todo));
+        assert!(matches!(line.status, ItemStatus::Complete));
+        assert_eq!(line.text, "[ ] inside");
+        let line = round_trip(true, ItemType::Todo, ItemStatus::Disabled, "off");
+        assert!(line.hidden);
+        assert!(matches!(line.status, ItemStatus::Disabled));
+        assert_eq!(line.indent, TAB_WIDTH);
+    }
+    #[test]
+    fn surrounding_spaces_are_kept() {
+        let line = round_trip(false, ItemType::Todo, ItemStatus::Incomplete, "  padded  ");
+        assert_eq!(line.text, "  padded  ");
+        let line = round_trip(false, ItemType::Note, ItemStatus::Incomplete, " note");
+        assert_eq!(line.text, " note");
+    }
+    #[test]
+    fn empty_text_is_kept() {
+        let line = round_trip(true, ItemType::Note, ItemStatus::Incomplete, "");
+        assert!(line.hidden && line.text.is_empty());
+        let line = round_trip(false, ItemType::Todo, ItemStatus::Incomplete, "");
+        assert!(matches!(line.item_type, ItemType::Todo) && line.text.is_empty());
+    }
+    #[test]
+    fn pasted_text_is_trimmed() {
+        let lines = parse("  * [x]  done  \n\n- plain\n", false);
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(lines[0].status, ItemStatus::Complete));
+        assert_eq!(lines[0].text, "done");
+        assert!(matches!(lines[1].item_type, ItemType::Todo));
+        assert!(!lines[1].hidden);
+    }
+}
@@ -1,28 +1,67 @@
 use {
     crate::{
-        ctx::Ctx,
+        ctx::Ctx, editor,
         nav::{ NavigateMap, Navigator, NavMode, },
+        win, wrap,
     },
-    crossterm::{ cursor, event::KeyEvent, execute, terminal },
+    crossterm::{ cursor, event::{ self, KeyEvent, }, execute, terminal },
     std::{
         io::{ Error as IOError, stdout as get_stdout, Stdout, },
-        sync::mpsc::Receiver,
+        sync::{ Arc, atomic::{ AtomicBool, Ordering, }, mpsc::Receiver, },
+        thread::sleep,
+        time::{ Duration, Instant, },
     },
     todo_core::GetPath,
-    tui::{ backend::CrosstermBackend, layout, Terminal, widgets, },
+    tui::{ backend::CrosstermBackend, layout, text, Terminal, widgets, },
 };
 pub enum TermEvent {
     Key(KeyEvent),
+    /// Text pasted while bracketed paste is enabled, delivered at once
+    Paste(String),
     Tick,
 }
+/// Stops the event thread from reading input while another program, such as
+/// the external editor, owns the terminal.
+#[derive(Default)]
+pub struct InputGate {
+    paused: AtomicBool,
+    /// Set by the event thread once it has seen the pause and stopped reading
+    idle: AtomicBool,
+}
+impl InputGate {
+    /// How long `pause` waits for the event thread before giving up
+    const PAUSE_TIMEOUT: Duration = Duration::from_secs(1);
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+    /// Called by the event thread when it finds the gate paused.
+    pub fn acknowledge(&self) {
+        self.idle.store(true, Ordering::SeqCst);
+    }
+    /// Pauses and waits until the event thread has stopped reading, so that
+    /// none of the next program's input ends up with us.
+    fn pause(&self) {
+        self.idle.store(false, Ordering::SeqCst);
+        self.paused.store(true, Ordering::SeqCst);
+        let start = Instant::now();
+        while !self.idle.load(Ordering::SeqCst) && start.elapsed() < Self::PAUSE_TIMEOUT {
+            sleep(Duration::from_millis(1));
+        }
+    }
+    fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+}
 pub struct TerminalManager {
     term: Terminal<CrosstermBackend<Stdout>>,
     event_rx: Receiver<TermEvent>,
     navigator: Navigator,
+    input: Arc<InputGate>,
 }
 impl TerminalManager {
     pub fn init(
-        ctx: &mut Ctx, mut out: Stdout, event_rx: Receiver<TermEvent>,
+        mut navigator: Navigator, mut out: Stdout, event_rx: Receiver<TermEvent>,
+        input: Arc<InputGate>,
     ) -> Result<Self, IOError> {
         execute!(
             &mut out,
@@ -31,18 +70,64 @@ impl TerminalManager {
         execute!(
             &mut out,
             terminal::EnterAlternateScreen,
+            event::EnableBracketedPaste,
         )?;
         terminal::enable_raw_mode()?;
         let term = tui::Terminal::new(CrosstermBackend::new(out))?;
-        let mut navigator = Navigator::new(ctx);
         navigator.push_log(
             format!("Hash initialized {}", navigator.file_hash)
         );
         Ok(Self {
             term, event_rx,
-            navigator,
+            navigator, input,
         })
     }
+    /// Hands the terminal back to the shell, as on exit, and stops reading
+    /// input.
+    fn suspend(&mut self) -> Result<(), IOError> {
+        self.input.pause();
+        terminal::disable_raw_mode()?;
+        execute!(
+            self.term.backend_mut(),
+            event::DisableBracketedPaste,
+            terminal::LeaveAlternateScreen,
+            cursor::Show,
+        )?;
+        Ok(())
+    }
+    fn resume(&mut self) -> Result<(), IOError> {
+        execute!(
+            self.term.backend_mut(),
+            cursor::Hide,
+            terminal::EnterAlternateScreen,
+            event::EnableBracketedPaste,
+        )?;
+        terminal::enable_raw_mode()?;
+        // whatever the editor drew is still in the terminal's buffer
+        self.term.clear()?;
+        self.input.resume();
+        Ok(())
+    }
+    /// Opens the requested item in the external editor and applies the
+    /// result.
+    fn run_editor(&mut self, ctx: &mut Ctx) {
+        let edit = match self.navigator.edit.take() {
+            Some(edit) => edit,
+            None => return,
+        };
+        let (contents, extension) = match self.navigator.edit_contents(&edit) {
+            Some(contents) => contents,
+            None => return,
+        };
+        if let Err(e) = self.suspend() {
+            self.navigator.push_error(format!("Failed to suspend the terminal: {}", e));
+        }
+        let result = editor::edit(&contents, extension);
+        if let Err(e) = self.resume() {
+            self.navigator.push_error(format!("Failed to restore the terminal: {}", e));
+        }
+        self.navigator.apply_edit(ctx, edit, result);
+    }
     pub fn run(&mut self, ctx: &mut Ctx) {
         let mut initial = true;
         let mut is_running = true;
@@ -66,69 +151,78 @@ impl TerminalManager {
                 } else {
                     false
                 };
-                let constraints = if self.navigator.debug && is_input_mode {
-                    [
-                        layout::Constraint::Min(3),
-                        layout::Constraint::Length(3),
-                        layout::Constraint::Length(6),
-                    ].as_ref()
-                } else if is_input_mode {
-                    [
-                        layout::Constraint::Min(3),
-                        layout::Constraint::Length(3),
-                    ].as_ref()
-                } else if self.navigator.debug {
-                    [
-                        layout::Constraint::Min(3),
-                        layout::Constraint::Length(6),
-                    ].as_ref()
-                } else {
-                    [
-                        layout::Constraint::Min(3),
-                    ].as_ref()
-                };
+                let mut constraints = vec![ layout::Constraint::Min(3), ];
+                if is_input_mode {
+                    constraints.push(layout::Constraint::Length(3));
+                }
+                if self.navigator.debug {
+                    constraints.push(layout::Constraint::Length(ctx.config.debug_height));
+                }
+                // status bar
+                constraints.push(layout::Constraint::Length(1));
                 let layout = layout::Layout::default()
                     .direction(layout::Direction::Vertical)
                     .margin(1)
                     .constraints(constraints)
                     .split(rect.size());
-                if !layout[0].height.eq(&(self.navigator.height)) {
-                    let y = layout[0].height.clone();
+                let (list_area, detail_area) = if self.navigator.detail {
+                    win::split_detail(layout[0])
+                } else {
+                    (layout[0], None)
+                };
+                if !list_area.height.eq(&(self.navigator.height)) {
+                    let y = list_area.height.clone();
                     self.navigator.height = y;
                     self.navigator.push_log(format!("Height set to {}", self.navigator.height));
-                    self.navigator.handle_win_buf(initial);
                 }
-                let list_items = self.navigator.get_list();
-                let list = widgets::List::new(list_items).block(
-                    widgets::Block::default()
-                        .borders(widgets::Borders::all())
-                        .title(ctx.get_path().to_str().unwrap())
-                        .title_alignment(layout::Alignment::Left)
+                if !list_area.width.eq(&(self.navigator.width)) {
+                    self.navigator.width = list_area.width;
+                    self.navigator.push_log(format!("Width set to {}", self.navigator.width));
+                }
+                // edits and resizes change how many rows items wrap to
+                let heights = self.navigator.row_heights();
+                self.navigator.handle_win_buf(initial, &heights);
+                let list_items = self.navigator.get_list(&heights);
+                let title = format!(
+                    "{}{}", ctx.get_path().to_str().unwrap(), self.navigator.list_progress(),
                 );
-                let text_box = widgets::Paragraph::new(
-                    format!("{}|", self.navigator.i_buffer.clone())
-                ).block(
+                let list = widgets::List::new(list_items).block(
                     widgets::Block::default()
                         .borders(widgets::Borders::all())
-                        .title("Text")
+                        .title(title)
                         .title_alignment(layout::Alignment::Left)
                 );
-                if self.navigator.debug && is_input_mode  {
-                    rect.render_widget(list, layout[0]);
-                    rect.render_widget(text_box, layout[1]);
-                    let buf_items = self.navigator.render_buffer();
-                    let buf_list = widgets::List::new(buf_items).block(
+                rect.render_widget(list, list_area);
+                if let Some(area) = detail_area {
+                    let detail_lines = self.navigator.detail_lines(
+                        area.width.saturating_sub(2) as usize,
+                        area.height.saturating_sub(2) as usize,
+                    );
+                    let detail = widgets::Paragraph::new(detail_lines).block(
                         widgets::Block::default()
-                        .borders(widgets::Borders::all())
-                        .title("Debug")
-                        .title_alignment(layout::Alignment::Left)
+                            .borders(widgets::Borders::all())
+                            .title("Details")
+                            .title_alignment(layout::Alignment::Left)
+                    );
+                    rect.render_widget(detail, area);
+                }
+                let mut next_area = 1;
+                if is_input_mode {
+                    // keep the end of a long input, where typing happens, in
+                    // view; one column goes to the cursor
+                    let text_width = layout[next_area].width.saturating_sub(3) as usize;
+                    let text_box = widgets::Paragraph::new(
+                        format!("{}|", wrap::tail(&self.navigator.i_buffer, text_width))
+                    ).block(
+                        widgets::Block::default()
+                            .borders(widgets::Borders::all())
+                            .title(self.navigator.input_title())
+                            .title_alignment(layout::Alignment::Left)
                     );
-                    rect.render_widget(buf_list, layout[2]);
-                } else if is_input_mode {
-                    rect.render_widget(list, layout[0]);
-                    rect.render_widget(text_box, layout[1]);
-                } else if self.navigator.debug {
-                    rect.render_widget(list, layout[0]);
+                    rect.render_widget(text_box, layout[next_area]);
+                    next_area = next_area + 1;
+                }
+                if self.navigator.debug {
                     let buf_items = self.navigator.render_buffer();
                     let buf_list = widgets::List::new(buf_items).block(
                         widgets::Block::default()
@@ -136,21 +230,65 @@ impl TerminalManager {
                         .title("Debug")
                         .title_alignment(layout::Alignment::Left)
                     );
-                    rect.render_widget(buf_list, layout[1]);
-                } else {
-                    rect.render_widget(list, layout[0]);
+                    rect.render_widget(buf_list, layout[next_area]);
+                    next_area = next_area + 1;
+                }
+                let status_bar = widgets::Paragraph::new(self.navigator.status_line());
+                rect.render_widget(status_bar, layout[next_area]);
+                if let Some(confirm) = &self.navigator.confirm {
+                    let hint = confirm.hint();
+                    let width = wrap::width(&confirm.prompt).max(wrap::width(&hint)) as u16 + 4;
+                    let area = win::centered_rect(width.max(30), 5, rect.size());
+                    let dialog = widgets::Paragraph::new(vec![
+                        text::Spans::from(confirm.prompt.clone()),
+                        text::Spans::from(""),
+                        text::Spans::from(hint),
+                    ])
+                        .alignment(layout::Alignment::Center)
+                        .block(
+                            widgets::Block::default()
+                                .borders(widgets::Borders::all())
+                                .title("Confirm")
+                                .title_alignment(layout::Alignment::Left)
+                        );
+                    rect.render_widget(widgets::Clear, area);
+                    rect.render_widget(dialog, area);
+                }
+                if self.navigator.mode.eq(&NavMode::Help) {
+                    let help_lines = self.navigator.help_lines();
+                    let area = win::centered_rect(
+                        64, help_lines.len() as u16 + 2, rect.size(),
+                    );
+                    // keep the last line of help at the bottom of the popup
+                    let max_scroll = (help_lines.len() as u16)
+                        .saturating_sub(area.height.saturating_sub(2));
+                    if self.navigator.help_scroll > max_scroll {
+                        self.navigator.help_scroll = max_scroll;
+                    }
+                    let help = widgets::Paragraph::new(help_lines)
+                        .scroll((self.navigator.help_scroll, 0))
+                        .block(
+                            widgets::Block::default()
+                                .borders(widgets::Borders::all())
+                                .title(self.navigator.help_title())
+                                .title_alignment(layout::Alignment::Left)
+                        );
+                    rect.render_widget(widgets::Clear, area);
+                    rect.render_widget(help, area);
                 }
             }).unwrap();
             initial = false;
             if !is_running {
                 break;
             }
+            self.run_editor(ctx);
         }
     }
     pub fn exit(&mut self) -> Result<(), IOError> {
         terminal::disable_raw_mode()?;
         execute!(
             self.term.backend_mut(),
+            event::DisableBracketedPaste,
             terminal::LeaveAlternateScreen,
         )?;
         execute!(
@@ -0,0 +1,179 @@
+use {
+    serde::Deserialize,
+    std::env,
+    tui::style::{ Color, Modifier, Style, },
+};
+/// Parses a color name, a `#rrggbb` truecolor value or a 256-color index.
+pub fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let r = u8::from_str_radix(hex.get(0..2)?, 16).ok()?;
+        let g = u8::from_str_radix(hex.get(2..4)?, 16).ok()?;
+        let b = u8::from_str_radix(hex.get(4..6)?, 16).ok()?;
+        return Some(Color::Rgb(r, g, b));
+    }
+    if let Ok(index) = name.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+    let color = match name.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+    Some(color)
+}
+/// Per-element color overrides from the `[colors]` table, applied on top of
+/// the selected theme
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorNames {
+    pub complete: Option<String>,
+    pub incomplete: Option<String>,
+    pub disabled: Option<String>,
+    pub note: Option<String>,
+    pub selected: Option<String>,
+    pub marked: Option<String>,
+    pub hidden: Option<String>,
+    pub text: Option<String>,
+    pub log: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+}
+#[derive(Clone)]
+pub struct Theme {
+    pub complete: Style,
+    pub incomplete: Style,
+    pub disabled: Style,
+    pub note: Style,
+    pub selected: Style,
+    /// Rows in the visual selection
+    pub marked: Style,
+    pub hidden: Style,
+    pub text: Style,
+    pub log: Style,
+    pub warning: Style,
+    pub error: Style,
+}
+impl Theme {
+    pub const NAMES: [&'static str; 3] = [ "dark", "light", "mono", ];
+    fn fg(color: Color) -> Style {
+        Style::default().fg(color)
+    }
+    pub fn dark() -> Self {
+        Self {
+            complete: Self::fg(Color::Green),
+            incomplete: Self::fg(Color::Red),
+            disabled: Self::fg(Color::Yellow),
+            note: Self::fg(Color::Cyan),
+            selected: Self::fg(Color::Cyan),
+            marked: Self::fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            hidden: Self::fg(Color::DarkGray),
+            text: Self::fg(Color::White),
+            log: Self::fg(Color::Cyan),
+            warning: Self::fg(Color::Yellow),
+            error: Self::fg(Color::Red),
+        }
+    }
+    pub fn light() -> Self {
+        Self {
+            complete: Self::fg(Color::Green),
+            incomplete: Self::fg(Color::Red),
+            disabled: Self::fg(Color::Magenta),
+            note: Self::fg(Color::Blue),
+            selected: Self::fg(Color::Blue).add_modifier(Modifier::BOLD),
+            marked: Self::fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            hidden: Self::fg(Color::Gray),
+            text: Self::fg(Color::Black),
+            log: Self::fg(Color::Blue),
+            warning: Self::fg(Color::Magenta),
+            error: Self::fg(Color::Red),
+        }
+    }
+    /// Relies on text attributes alone, for terminals without color or when
+    /// `NO_COLOR` is set.
+    pub fn mono() -> Self {
+        let plain = Style::default();
+        Self {
+            complete: plain,
+            incomplete: plain.add_modifier(Modifier::BOLD),
+            disabled: plain.add_modifier(Modifier::DIM),
+            note: plain.add_modifier(Modifier::ITALIC),
+            selected: plain.add_modifier(Modifier::REVERSED),
+            marked: plain.add_modifier(Modifier::UNDERLINED),
+            hidden: plain.add_modifier(Modifier::DIM),
+            text: plain,
+            log: plain,
+            warning: plain.add_modifier(Modifier::BOLD),
+            error: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "mono" | "monochrome" => Some(Self::mono()),
+            _ => None,
+        }
+    }
+    /// Whether color output has been disabled through `NO_COLOR`
+    pub fn no_color() -> bool {
+        env::var_os("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false)
+    }
+    /// Builds the theme named `name` with the config's overrides applied, or
+    /// the monochrome theme when `NO_COLOR` is set.
+    pub fn load(name: &str, colors: &ColorNames) -> Result<Self, String> {
+        let mut theme = Self::from_name(name).ok_or(format!(
+            "theme: unknown theme \"{}\", expected one of {}", name, Self::NAMES.join(", ")
+        ))?;
+        if Self::no_color() {
+            return Ok(Self::mono());
+        }
+        let overrides = [
+            ("complete", &colors.complete, &mut theme.complete),
+            ("incomplete", &colors.incomplete, &mut theme.incomplete),
+            ("disabled", &colors.disabled, &mut theme.disabled),
+            ("note", &colors.note, &mut theme.note),
+            ("selected", &colors.selected, &mut theme.selected),
+            ("marked", &colors.marked, &mut theme.marked),
+            ("hidden", &colors.hidden, &mut theme.hidden),
+            ("text", &colors.text, &mut theme.text),
+            ("log", &colors.log, &mut theme.log),
+            ("warning", &colors.warning, &mut theme.warning),
+            ("error", &colors.error, &mut theme.error),
+        ];
+        for (key, name, style) in overrides {
+            if let Some(name) = name {
+                let color = parse_color(name)
+                    .ok_or(format!("colors.{}: unknown color \"{}\"", key, name))?;
+                *style = style.fg(color);
+            }
+        }
+        Ok(theme)
+    }
+}
+impl Default for Theme {
+    fn default() -> Self {
+        if Self::no_color() {
+            Self::mono()
+        } else {
+            Self::dark()
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use todo_core::{ Container, Item, ItemAction, ItemActor, ItemStatus, ItemType, };
+pub trait PrintCoords {
+    fn to_coords(&self) -> String;
+}
+impl PrintCoords for Vec<usize> {
+    fn to_coords(&self) -> String {
+        let mut out = String::new();
+        let mut delim = "";
+        for item in self {
+            out.push_str(&format!("{}{}", delim, item));
+            delim = ",";
+        }
+        out
+    }
+}
+/// Callbacks for a depth-first walk over a list's items.
+pub trait ItemVisitor {
+    fn enter(&mut self, item: &Item, pos: &Vec<usize>);
+    fn leave(&mut self, _item: &Item, _pos: &Vec<usize>) {}
+}
+/// Anything holding a list of children the way items do, so that the
+/// functions moving items around can be tried on plain values.
+pub trait Nested: Sized {
+    fn sub_items(&self) -> &Vec<Self>;
+    fn sub_items_mut(&mut self) -> &mut Vec<Self>;
+}
+impl Nested for Item {
+    fn sub_items(&self) -> &Vec<Self> {
+        &self.sub_items
+    }
+    fn sub_items_mut(&mut self) -> &mut Vec<Self> {
+        &mut self.sub_items
+    }
+}
+/// Converts a zero-based position (as stored in the navigation map) into the
+/// reversed, one-based location expected by `ItemActor::act_on_item_at`.
+pub fn to_location(pos: &Vec<usize>) -> Vec<usize> {
+    let mut location = pos.clone();
+    location.reverse();
+    location.iter_mut().for_each(|item| {
+        *item = *item + 1;
+    });
+    location
+}
+pub fn item_at<'a, T: Nested>(items: &'a Vec<T>, pos: &[usize]) -> Option<&'a T> {
+    let (first, rest) = pos.split_first()?;
+    let item = items.get(*first)?;
+    if rest.is_empty() {
+        Some(item)
+    } else {
+        item_at(item.sub_items(), rest)
+    }
+}
+pub fn item_at_mut<'a, T: Nested>(items: &'a mut Vec<T>, pos: &[usize]) -> Option<&'a mut T> {
+    let (first, rest) = pos.split_first()?;
+    let item = items.get_mut(*first)?;
+    if rest.is_empty() {
+        Some(item)
+    } else {
+        item_at_mut(item.sub_items_mut(), rest)
+    }
+}
+/// Returns the list holding the children of `parent`, or the root list when
+/// `parent` is empty.
+pub fn children_at<'a, T: Nested>(items: &'a Vec<T>, parent: &[usize]) -> Option<&'a Vec<T>> {
+    if parent.is_empty() {
+        return Some(items);
+    }
+    item_at(items, parent).map(|item| item.sub_items())
+}
+pub fn children_at_mut<'a, T: Nested>(
+    items: &'a mut Vec<T>, parent: &[usize]
+) -> Option<&'a mut Vec<T>> {
+    if parent.is_empty() {
+        return Some(items);
+    }
+    item_at_mut(items, parent).map(|item| item.sub_items_mut())
+}
+/// Appends a new item as the last child of `parent` and returns its
+/// zero-based position.
+pub fn push_item(
+    container: &mut Container, parent: &Vec<usize>, item_type: ItemType,
+    text: impl AsRef<str>,
+) -> Option<Vec<usize>> {
+    let mut location = to_location(parent);
+    container.act_on_item_at(
+        &mut location,
+        ItemAction::Add(item_type, text.as_ref().to_string()),
+    );
+    let len = children_at(&container.list.items, parent)?.len();
+    if len == 0 {
+        return None;
+    }
+    let mut pos = parent.clone();
+    pos.push(len - 1);
+    Some(pos)
+}
+/// Adds a new item among the children of `parent` at `index`, shifting the
+/// ones after it down, and returns its zero-based position.
+pub fn insert_item(
+    container: &mut Container, parent: &Vec<usize>, index: usize, item_type: ItemType,
+    text: impl AsRef<str>,
+) -> Option<Vec<usize>> {
+    let pushed = push_item(container, parent, item_type, text)?;
+    let siblings = children_at_mut(&mut container.list.items, parent)?;
+    let index = index.min(siblings.len() - 1);
+    let item = siblings.pop()?;
+    siblings.insert(index, item);
+    let mut pos = pushed;
+    pos.pop();
+    pos.push(index);
+    Some(pos)
+}
+fn walk_items(
+    items: &Vec<Item>, display_hidden: bool, pos: &mut Vec<usize>,
+    visitor: &mut impl ItemVisitor,
+) {
+    let mut i = 0;
+    for item in items.iter() {
+        pos.push(i);
+        if display_hidden || !item.hidden {
+            visitor.enter(item, pos);
+            walk_items(&item.sub_items, display_hidden, pos, visitor);
+            visitor.leave(item, pos);
+        }
+        pos.pop();
+        i = i + 1;
+    }
+}
+/// Visits every item in display order. Hidden items, along with their
+/// children, are skipped unless `display_hidden` is set.
+pub fn walk(items: &Vec<Item>, display_hidden: bool, visitor: &mut impl ItemVisitor) {
+    let mut pos = Vec::new();
+    walk_items(items, display_hidden, &mut pos, visitor);
+}
+/// Counts the complete and total todo items among `items` and all of their
+/// descendants, leaving disabled items out of both when `ignore_disabled` is
+/// set.
+pub fn progress(items: &Vec<Item>, ignore_disabled: bool) -> (usize, usize) {
+    let mut complete = 0;
+    let mut total = 0;
+    for item in items.iter() {
+        if let ItemType::Todo = item.item_type {
+            match item.status {
+                ItemStatus::Complete => {
+                    complete = complete + 1;
+                    total = total + 1;
+                },
+                ItemStatus::Incomplete => total = total + 1,
+                ItemStatus::Disabled => if !ignore_disabled {
+                    total = total + 1;
+                },
+            }
+        }
+        let (sub_complete, sub_total) = progress(&item.sub_items, ignore_disabled);
+        complete = complete + sub_complete;
+        total = total + sub_total;
+    }
+    (complete, total)
+}
+/// Totals shown in the status bar
+#[derive(Default)]
+pub struct Counts {
+    pub todos: usize,
+    pub complete: usize,
+    pub incomplete: usize,
+    pub hidden: usize,
+}
+/// Tallies `items` and all of their descendants, hidden ones included.
+pub fn counts(items: &Vec<Item>) -> Counts {
+    let mut counts = Counts::default();
+    add_counts(items, &mut counts);
+    counts
+}
+fn add_counts(items: &Vec<Item>, counts: &mut Counts) {
+    for item in items.iter() {
+        if let ItemType::Todo = item.item_type {
+            counts.todos = counts.todos + 1;
+            match item.status {
+                ItemStatus::Complete => counts.complete = counts.complete + 1,
+                ItemStatus::Incomplete => counts.incomplete = counts.incomplete + 1,
+                ItemStatus::Disabled => {},
+            }
+        }
+        if item.hidden {
+            counts.hidden = counts.hidden + 1;
+        }
+        add_counts(&item.sub_items, counts);
+    }
+}
+/// Number of items below `items`, counting every level
+pub fn descendant_count(items: &Vec<Item>) -> usize {
+    items.iter().fold(0, |count, item| count + 1 + descendant_count(&item.sub_items))
+}
+pub fn is_complete(item: &Item) -> bool {
+    matches!((&item.item_type, &item.status), (ItemType::Todo, ItemStatus::Complete))
+}
+fn collect_complete(items: &Vec<Item>, pos: &mut Vec<usize>, found: &mut Vec<Vec<usize>>) {
+    let mut i = 0;
+    for item in items.iter() {
+        pos.push(i);
+        if is_complete(item) {
+            // its children go along with it
+            found.push(pos.clone());
+        } else {
+            collect_complete(&item.sub_items, pos, found);
+        }
+        pos.pop();
+        i = i + 1;
+    }
+}
+/// Positions of the completed items below `parent` (the whole list when it is
+/// empty), leaving out those below a completed ancestor. Positions are in
+/// display order.
+pub fn completed_under(items: &Vec<Item>, parent: &Vec<usize>) -> Vec<Vec<usize>> {
+    let mut found = Vec::new();
+    if let Some(children) = children_at(items, parent) {
+        let mut pos = parent.clone();
+        collect_complete(children, &mut pos, &mut found);
+    }
+    found
+}
+/// Removes the items at `positions`, which must be in display order, and
+/// returns them in that same order.
+pub fn remove_items<T: Nested>(items: &mut Vec<T>, positions: &Vec<Vec<usize>>) -> Vec<T> {
+    let mut removed = Vec::new();
+    // remove from the bottom up so earlier positions stay valid
+    for pos in positions.iter().rev() {
+        if let Some((index, parent)) = pos.split_last() {
+            if let Some(siblings) = children_at_mut(items, parent) {
+                if *index < siblings.len() {
+                    removed.push(siblings.remove(*index));
+                }
+            }
+        }
+    }
+    removed.reverse();
+    removed
+}
+/// Drops the positions that lie below another position in `positions`, so
+/// that every item is only acted on once along with its children.
+pub fn top_most(positions: &Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    positions.iter()
+        .filter(|pos| !positions.iter().any(|other| other.len() < pos.len() && pos.starts_with(other)))
+        .cloned()
+        .collect()
+}
+/// Splits top-most positions in display order into runs of siblings, keeping
+/// display order within and between the groups.
+fn sibling_groups(positions: &Vec<Vec<usize>>) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let mut groups: Vec<(Vec<usize>, Vec<usize>)> = Vec::new();
+    for pos in positions.iter() {
+        let (index, parent) = match pos.split_last() {
+            Some((index, parent)) => (*index, parent.to_vec()),
+            None => continue,
+        };
+        match groups.iter_mut().find(|(group_parent, _)| group_parent.eq(&parent)) {
+            Some((_, indices)) => indices.push(index),
+            None => groups.push((parent, vec![index])),
+        }
+    }
+    groups
+}
+/// Makes each item at `positions` (in display order) the last child of its
+/// previous sibling. Runs of siblings all move into the same item. Returns
+/// the new position of the first item.
+pub fn indent_items<T: Nested>(
+    items: &mut Vec<T>, positions: &Vec<Vec<usize>>,
+) -> Result<Vec<usize>, String> {
+    let groups = sibling_groups(&top_most(positions));
+    if groups.iter().any(|(_, indices)| indices.first().eq(&Some(&0))) {
+        return Err("The first item of a level cannot be indented".to_string());
+    }
+    let mut first = Vec::new();
+    // later groups first, so that the positions of earlier ones stay valid
+    for (parent, indices) in groups.iter().rev() {
+        let siblings = children_at_mut(items, parent)
+            .ok_or("Failed to find the items to indent".to_string())?;
+        let mut moved = 0;
+        for index in indices.iter() {
+            let index = index - moved;
+            let item = siblings.remove(index);
+            let target = siblings[index - 1].sub_items_mut();
+            target.push(item);
+            if moved == 0 {
+                first = parent.clone();
+                first.push(index - 1);
+                first.push(target.len() - 1);
+            }
+            moved = moved + 1;
+        }
+    }
+    Ok(first)
+}
+/// Moves each item at `positions` (in display order) out of its parent to
+/// just after it, keeping the order of the moved items. Returns the new
+/// position of the first item.
+pub fn outdent_items<T: Nested>(
+    items: &mut Vec<T>, positions: &Vec<Vec<usize>>,
+) -> Result<Vec<usize>, String> {
+    let groups = sibling_groups(&top_most(positions));
+    if groups.iter().any(|(parent, _)| parent.is_empty()) {
+        return Err("Root items cannot be outdented".to_string());
+    }
+    let mut first = Vec::new();
+    for (parent, indices) in groups.iter().rev() {
+        let (parent_index, grandparent) = match parent.split_last() {
+            Some((index, grandparent)) => (*index, grandparent.to_vec()),
+            None => continue,
+        };
+        let moved = {
+            let siblings = children_at_mut(items, parent)
+                .ok_or("Failed to find the items to outdent".to_string())?;
+            let mut moved = Vec::new();
+            for index in indices.iter().rev() {
+                moved.push(siblings.remove(*index));
+            }
+            moved
+        };
+        let target = children_at_mut(items, &grandparent)
+            .ok_or("Failed to find the parent level".to_string())?;
+        // `moved` is in reverse order, inserting each at the same index
+        // restores it
+        for item in moved {
+            target.insert(parent_index + 1, item);
+        }
+        first = grandparent;
+        first.push(parent_index + 1);
+    }
+    Ok(first)
+}
+/// Swaps each item at `positions` (in display order) with its next sibling
+/// when `down` is set, or its previous one otherwise. Returns the new
+/// position of the first item.
+pub fn move_items<T: Nested>(
+    items: &mut Vec<T>, positions: &Vec<Vec<usize>>, down: bool,
+) -> Result<Vec<usize>, String> {
+    let groups = sibling_groups(&top_most(positions));
+    for (parent, indices) in groups.iter() {
+        let len = children_at(items, parent).map(|siblings| siblings.len()).unwrap_or(0);
+        let at_edge = if down {
+            indices.iter().any(|index| index + 1 >= len)
+        } else {
+            indices.iter().any(|index| *index == 0)
+        };
+        if at_edge {
+            return Err(format!("Cannot move past the {} of a level", if down { "end" } else { "start" }));
+        }
+    }
+    // a swap only moves the items below the swapped pair, so deeper groups go
+    // first and a group's parent is never moved before the group is handled
+    let mut ordered = groups.iter().collect::<Vec<_>>();
+    ordered.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+    // follow the first item through every swap, its ancestors included
+    let mut first = top_most(positions).first().cloned().unwrap_or_default();
+    for (parent, indices) in ordered.into_iter() {
+        let siblings = children_at_mut(items, parent)
+            .ok_or("Failed to find the items to move".to_string())?;
+        let mut sorted = indices.clone();
+        sorted.sort();
+        if down {
+            sorted.reverse();
+        }
+        for index in sorted.iter() {
+            let other = if down { index + 1 } else { index.wrapping_sub(1) };
+            if *index >= siblings.len() || other >= siblings.len() {
+                return Err("Failed to find the items to move".to_string());
+            }
+            siblings.swap(*index, other);
+            if first.len() > parent.len() && first.starts_with(parent) {
+                let depth = parent.len();
+                if first[depth] == *index {
+                    first[depth] = other;
+                } else if first[depth] == other {
+                    first[depth] = *index;
+                }
+            }
+        }
+    }
+    Ok(first)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    struct Node {
+        id: usize,
+        sub_items: Vec<Node>,
+    }
+    impl Nested for Node {
+        fn sub_items(&self) -> &Vec<Self> {
+            &self.sub_items
+        }
+        fn sub_items_mut(&mut self) -> &mut Vec<Self> {
+            &mut self.sub_items
+        }
+    }
+    fn node(id: usize, sub_items: Vec<Node>) -> Node {
+        Node { id, sub_items, }
+    }
+    /// `1,2(3,4)` for a list of 1 and 2, with 3 and 4 below 2
+    fn shape(items: &Vec<Node>) -> String {
+        items.iter().map(|item| {
+            if item.sub_items.is_empty() {
+                item.id.to_string()
+            } else {
+                format!("{}({})", item.id, shape(&item.sub_items))
+            }
+        }).collect::<Vec<String>>().join(",")
+    }
+    /// 1,2,3,4
+    fn flat() -> Vec<Node> {
+        vec![ node(1, vec![]), node(2, vec![]), node(3, vec![]), node(4, vec![]), ]
+    }
+    /// 1,2(3,4(5))
+    fn nested() -> Vec<Node> {
+        vec![
+            node(1, vec![]),
+            node(2, vec![ node(3, vec![]), node(4, vec![ node(5, vec![]), ]), ]),
+        ]
+    }
+    #[test]
+    fn indent_non_adjacent() {
+        let mut items = flat();
+        assert_eq!(indent_items(&mut items, &vec![ vec![1], vec![3], ]), Ok(vec![0, 0]));
+        assert_eq!(shape(&items), "1(2),3(4)");
+    }
+    #[test]
+    fn indent_run_into_one_item() {
+        let mut items = flat();
+        assert_eq!(indent_items(&mut items, &vec![ vec![2], vec![3], ]), Ok(vec![1, 0]));
+        assert_eq!(shape(&items), "1,2(3,4)");
+    }
+    #[test]
+    fn indent_nested() {
+        let mut items = nested();
+        assert_eq!(indent_items(&mut items, &vec![ vec![1, 1], ]), Ok(vec![1, 0, 0]));
+        assert_eq!(shape(&items), "1,2(3(4(5)))");
+    }
+    #[test]
+    fn indent_parent_with_child() {
+        let mut items = nested();
+        let positions = vec![ vec![1], vec![1, 0], ];
+        assert_eq!(indent_items(&mut items, &positions), Ok(vec![0, 0]));
+        assert_eq!(shape(&items), "1(2(3,4(5)))");
+    }
+    #[test]
+    fn indent_first_item_fails() {
+        let mut items = nested();
+        assert!(indent_items(&mut items, &vec![ vec![0], ]).is_err());
+        assert!(indent_items(&mut items, &vec![ vec![1, 0], vec![1, 1], ]).is_err());
+        assert_eq!(shape(&items), "1,2(3,4(5))");
+    }
+    #[test]
+    fn outdent_non_adjacent() {
+        let mut items = vec![
+            node(1, vec![ node(2, vec![]), node(3, vec![]), node(4, vec![]), ]),
+        ];
+        assert_eq!(outdent_items(&mut items, &vec![ vec![0, 0], vec![0, 2], ]), Ok(vec![1]));
+        assert_eq!(shape(&items), "1(3),2,4");
+    }
+    #[test]
+    fn outdent_nested() {
+        let mut items = nested();
+        assert_eq!(outdent_items(&mut items, &vec![ vec![1, 1, 0], ]), Ok(vec![1, 2]));
+        assert_eq!(shape(&items), "1,2(3,4,5)");
+    }
+    #[test]
+    fn outdent_parent_with_child() {
+        let mut items = nested();
+        let positions = vec![ vec![1, 1], vec![1, 1, 0], ];
+        assert_eq!(outdent_items(&mut items, &positions), Ok(vec![2]));
+        assert_eq!(shape(&items), "1,2(3),4(5)");
+    }
+    #[test]
+    fn outdent_root_item_fails() {
+        let mut items = nested();
+        assert!(outdent_items(&mut items, &vec![ vec![0], ]).is_err());
+        assert!(outdent_items(&mut items, &vec![ vec![1], vec![1, 0], ]).is_err());
+        assert_eq!(shape(&items), "1,2(3,4(5))");
+    }
+    #[test]
+    fn move_non_adjacent() {
+        let mut items = flat();
+        assert_eq!(move_items(&mut items, &vec![ vec![0], vec![2], ], true), Ok(vec![1]));
+        assert_eq!(shape(&items), "2,1,4,3");
+    }
+    #[test]
+    fn move_run() {
+        let mut items = flat();
+        assert_eq!(move_items(&mut items, &vec![ vec![1], vec![2], ], false), Ok(vec![0]));
+        assert_eq!(shape(&items), "2,3,1,4");
+        assert_eq!(move_items(&mut items, &vec![ vec![0], vec![1], ], true), Ok(vec![1]));
+        assert_eq!(shape(&items), "1,2,3,4");
+    }
+    #[test]
+    fn move_nested() {
+        let mut items = nested();
+        let positions = vec![ vec![0], vec![1, 0], ];
+        assert_eq!(move_items(&mut items, &positions, true), Ok(vec![1]));
+        assert_eq!(shape(&items), "2(4(5),3),1");
+    }
+    #[test]
+    fn move_parent_with_child() {
+        let mut items = nested();
+        let positions = vec![ vec![1], vec![1, 1], ];
+        assert_eq!(move_items(&mut items, &positions, false), Ok(vec![0]));
+        assert_eq!(shape(&items), "2(3,4(5)),1");
+    }
+    #[test]
+    fn move_past_edge_fails() {
+        let mut items = nested();
+        assert!(move_items(&mut items, &vec![ vec![0], ], false).is_err());
+        assert!(move_items(&mut items, &vec![ vec![1], ], true).is_err());
+        // nothing moves when only one of the items is at the edge
+        assert!(move_items(&mut items, &vec![ vec![0], vec![1, 1], ], true).is_err());
+        assert_eq!(shape(&items), "1,2(3,4(5))");
+    }
+}
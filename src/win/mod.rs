@@ -1,12 +1,20 @@
+use tui::layout::{ Constraint, Direction, Layout, Rect, };
+/// The rows of the list in view. Items may span several rows, so the window
+/// is tracked in rows and the selection by its index among the items.
 pub struct WindowBufferBounds {
     pub size: u16,
+    /// First row in view
     pub min: u16,
+    /// Last row in view
     pub max: u16,
+    /// Index of the selected item
     pub pos: u16,
 }
 impl WindowBufferBounds {
     // The drawn borders for the Y-bounds take up 1 line apiece
     const Y_PADDING: u16 = 2;
+    // as do the ones for the X-bounds
+    pub const X_PADDING: u16 = 2;
     pub fn init() -> Self {
         Self {
             size: 0,
@@ -15,48 +23,79 @@ impl WindowBufferBounds {
             pos: 0,
         }
     }
-    pub fn set_size(&mut self, size: u16, pos: &Vec<usize>, valid_pos: &Vec<Vec<usize>>, initial: bool) -> bool {
+    /// Scrolls as little as possible to bring every row of the selected item
+    /// into view, or its first rows when it is taller than the window.
+    /// `heights` holds the rows taken by each item in `valid_pos`.
+    pub fn set_size(
+        &mut self, size: u16, pos: &Vec<usize>, valid_pos: &Vec<Vec<usize>>, heights: &Vec<u16>,
+        initial: bool,
+    ) -> bool {
         let mut changed = false;
-        let new_size = size - Self::Y_PADDING;
+        let new_size = size.saturating_sub(Self::Y_PADDING);
         if self.size != new_size {
             self.size = new_size;
             changed = true;
         }
-        if self.size > 0 && self.max == 0 {
-            self.min = 0;
-            self.max = self.size - 1;
-            changed = true;
+        if self.size == 0 {
+            return changed;
         }
-        if initial {
-            self.pos = 0;
-            changed = true;
+        let index = if initial {
+            0
         } else {
-            // calculate absolute position
-            let mut absolute_pos = 0;
-            for position in valid_pos.iter() {
-                if position == pos {
-                    break;
-                }
-                absolute_pos = absolute_pos + 1;
-            }
-            if self.pos != absolute_pos {
-                self.pos = absolute_pos as u16;
-                changed = true;
-            }
-        }
-        // set min/max
-        if self.pos >= self.max {
-            self.max = self.pos;
-            self.min = self.max - (self.size - 1);
+            valid_pos.iter().position(|position| position == pos).unwrap_or(0)
+        };
+        if self.pos != index as u16 {
+            self.pos = index as u16;
             changed = true;
-        } else if self.pos < self.min {
-            self.min = self.pos;
-            self.max = self.min + (self.size - 1);
+        }
+        let start = heights.iter().take(index).sum::<u16>();
+        let end = start + heights.get(index).copied().unwrap_or(1).max(1) - 1;
+        let mut min = if initial { 0 } else { self.min };
+        if end >= min + self.size {
+            min = end + 1 - self.size;
+        }
+        if start < min {
+            min = start;
+        }
+        let max = min + self.size - 1;
+        if self.min != min || self.max != max {
+            self.min = min;
+            self.max = max;
             changed = true;
         }
         changed
     }
-    pub fn is_in_view(&self, line: u16) -> bool {
-        line > self.min && line <= (self.max + 1)
+    pub fn is_in_view(&self, row: u16) -> bool {
+        row >= self.min && row <= self.max
+    }
+}
+/// Splits the list area to make room for the detail pane: to the right when
+/// there is room for both side by side, below the list otherwise, and not at
+/// all when the area is too small for either.
+pub fn split_detail(area: Rect) -> (Rect, Option<Rect>) {
+    const MIN_SIDE_WIDTH: u16 = 80;
+    const MIN_STACKED_HEIGHT: u16 = 16;
+    let (direction, constraints) = if area.width >= MIN_SIDE_WIDTH {
+        (Direction::Horizontal, [ Constraint::Percentage(60), Constraint::Percentage(40), ])
+    } else if area.height >= MIN_STACKED_HEIGHT {
+        (Direction::Vertical, [ Constraint::Percentage(60), Constraint::Percentage(40), ])
+    } else {
+        return (area, None);
+    };
+    let split = Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(area);
+    (split[0], Some(split[1]))
+}
+/// Returns a rectangle of at most `width` by `height` centered within `area`.
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
     }
 }
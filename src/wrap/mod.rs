@@ -0,0 +1,160 @@
+use {
+    unicode_segmentation::UnicodeSegmentation,
+    unicode_width::UnicodeWidthStr,
+};
+/// Columns taken up by `text` on screen. Wide characters such as CJK and
+/// most emoji take two, combining marks none.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+/// Splits `text` into lines of at most `max_width` columns, breaking at
+/// spaces where possible and between grapheme clusters inside words that
+/// don't fit on a line of their own. A cluster wider than `max_width` gets a
+/// line to itself. Leading spaces are kept, the ones at a break are dropped.
+/// Always returns at least one line.
+pub fn wrap(text: &str, max_width: usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    // whether a word, even an empty one between two spaces, is on the line
+    let mut started = false;
+    for word in text.split(' ') {
+        let word_width = width(word);
+        let needed = if started { line_width + 1 + word_width } else { word_width };
+        if needed <= max_width {
+            if started {
+                line.push(' ');
+                line_width = line_width + 1;
+            }
+            line.push_str(word);
+            line_width = line_width + word_width;
+            started = true;
+            continue;
+        }
+        if started {
+            lines.push(line);
+            line = String::new();
+            line_width = 0;
+            started = false;
+        }
+        for grapheme in word.graphemes(true) {
+            let grapheme_width = width(grapheme);
+            if line_width + grapheme_width > max_width && !line.is_empty() {
+                lines.push(line);
+                line = String::new();
+                line_width = 0;
+            }
+            line.push_str(grapheme);
+            line_width = line_width + grapheme_width;
+            started = true;
+        }
+    }
+    if started || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+/// Cuts `text` down to at most `max_width` columns, marking the cut with an
+/// ellipsis, without splitting a grapheme cluster.
+pub fn truncate(text: &str, max_width: usize) -> String {
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut out_width = 0;
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = width(grapheme);
+        // leave a column for the ellipsis
+        if out_width + grapheme_width + 1 > max_width {
+            break;
+        }
+        out.push_str(grapheme);
+        out_width = out_width + grapheme_width;
+    }
+    out.push('…');
+    out
+}
+/// The longest end of `text` that fits in `max_width` columns, for showing
+/// the part of a long input next to the cursor.
+pub fn tail(text: &str, max_width: usize) -> &str {
+    let mut start = text.len();
+    let mut tail_width = 0;
+    for (i, grapheme) in text.grapheme_indices(true).rev() {
+        let grapheme_width = width(grapheme);
+        if tail_width + grapheme_width > max_width {
+            break;
+        }
+        tail_width = tail_width + grapheme_width;
+        start = i;
+    }
+    &text[start..]
+}
+/// Removes the last grapheme cluster, so that a base character goes along
+/// with its combining marks and an emoji sequence goes as a whole.
+pub fn pop_grapheme(text: &mut String) {
+    if let Some((i, _)) = text.grapheme_indices(true).next_back() {
+        text.truncate(i);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const FAMILY: &str = "\u{1F469}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    const FLAGS: &str = "\u{1F1EF}\u{1F1F5}\u{1F1EB}\u{1F1F7}";
+    #[test]
+    fn width_counts_columns() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("日本語"), 6);
+        assert_eq!(width("e\u{301}"), 1);
+    }
+    #[test]
+    fn wrap_breaks_at_spaces_by_width() {
+        assert_eq!(wrap("日本 語", 4), vec![ "日本", "語", ]);
+        assert_eq!(wrap("cafe\u{301} bar", 4), vec![ "cafe\u{301}", "bar", ]);
+        assert_eq!(wrap("", 4), vec![ "", ]);
+    }
+    #[test]
+    fn wrap_keeps_leading_spaces() {
+        assert_eq!(wrap("  a b", 10), vec![ "  a b", ]);
+        assert_eq!(wrap("  日本 語", 6), vec![ "  日本", "語", ]);
+        assert_eq!(wrap("ab  cd", 2), vec![ "ab", "cd", ]);
+    }
+    #[test]
+    fn wrap_splits_long_words_between_clusters() {
+        assert_eq!(wrap("abcdef", 4), vec![ "abcd", "ef", ]);
+        // each cluster is wider than the line, so it gets one to itself
+        assert_eq!(wrap("日本", 1), vec![ "日", "本", ]);
+        assert_eq!(wrap(FAMILY, 1), vec![ FAMILY, ]);
+        assert_eq!(wrap(FLAGS, 1), vec![ "\u{1F1EF}\u{1F1F5}", "\u{1F1EB}\u{1F1F7}", ]);
+    }
+    #[test]
+    fn truncate_keeps_clusters_whole() {
+        assert_eq!(truncate("日本語", 6), "日本語");
+        assert_eq!(truncate("日本語", 5), "日本…");
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("cafe\u{301}s", 4), "caf…");
+    }
+    #[test]
+    fn tail_fits_the_end() {
+        assert_eq!(tail("ab日本", 3), "本");
+        assert_eq!(tail("ab日本", 4), "日本");
+        assert_eq!(tail("ae\u{301}", 1), "e\u{301}");
+        assert_eq!(tail("abc", 0), "");
+    }
+    #[test]
+    fn pop_grapheme_removes_whole_clusters() {
+        let mut text = "cafe\u{301}".to_string();
+        pop_grapheme(&mut text);
+        assert_eq!(text, "caf");
+        let mut text = format!("a{}", FLAGS);
+        pop_grapheme(&mut text);
+        assert_eq!(text, "a\u{1F1EF}\u{1F1F5}");
+        let mut text = format!("x{}", FAMILY);
+        pop_grapheme(&mut text);
+        assert_eq!(text, "x");
+        pop_grapheme(&mut text);
+        pop_grapheme(&mut text);
+        assert_eq!(text, "");
+    }
+}